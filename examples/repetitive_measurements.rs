@@ -7,8 +7,6 @@
 extern crate fine_grained;
 
 use fine_grained::Stopwatch;
-use fine_grained::Running;
-use fine_grained::Stopped;
 
 fn main() {
     // Get a new stopwatch and start it.
@@ -25,7 +23,7 @@ fn main() {
     println!();
 
     // Print the timing results.
-    for (i, &lap) in stopwatch.laps().into_iter().enumerate() {
+    for (i, &lap) in stopwatch.laps().iter().enumerate() {
         println!("   Round {i}:  {duration}ns", i = i, duration = lap);
     }
     println!("Total time: {duration}", duration = stopwatch);
@@ -0,0 +1,52 @@
+// Copyright 2017 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! An iterator over the laps of a stopwatch.
+
+use std::slice;
+
+/// An iterator over the measured lap times of a [`Stopwatch`](struct.Stopwatch.html), in nanoseconds.
+///
+/// The laps are yielded in the order they were timed. Since the number of laps is known in advance, the iterator
+/// can also be traversed in reverse and knows its exact length.
+#[derive(Clone, Debug)]
+pub struct Laps<'a> {
+    /// The iterator over the underlying list of laps.
+    iter: slice::Iter<'a, u64>,
+}
+
+impl<'a> Laps<'a> {
+    /// Create a new iterator over the given laps.
+    pub(crate) fn new(laps: &'a [u64]) -> Laps<'a> {
+        Laps {
+            iter: laps.iter(),
+        }
+    }
+}
+
+impl<'a> Iterator for Laps<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        self.iter.next().cloned()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for Laps<'a> {
+    fn next_back(&mut self) -> Option<u64> {
+        self.iter.next_back().cloned()
+    }
+}
+
+impl<'a> ExactSizeIterator for Laps<'a> {
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
@@ -17,6 +17,15 @@
 //! fine_grained = "0.1"
 //! ```
 //!
+//! # Features
+//!
+//! * `quanta`: Use the [`quanta`](https://crates.io/crates/quanta) crate's high-resolution clock in
+//!   `Stopwatch::lap_precise()`.
+//! * `rustfft`: Detect periodic patterns in lap times with `Stopwatch::dominant_period()`, using the
+//!   [`rustfft`](https://crates.io/crates/rustfft) crate.
+//! * `threading`: Periodically report snapshots of a shared stopwatch on a background thread with
+//!   `Stopwatch::spawn_reporter()`.
+//!
 //! # Examples
 //!
 //! The `examples` directory contains these examples if you want to actually run them.
@@ -62,7 +71,7 @@
 //!     let stopwatch = stopwatch.stop();
 //!
 //!     // Print the timing results.
-//!     for (i, &lap) in stopwatch.laps().into_iter().enumerate() {
+//!     for (i, &lap) in stopwatch.laps().iter().enumerate() {
 //!         println!("   Round {i}:  {duration}ns", i = i, duration = lap);
 //!     }
 //!     println!("Total time: {duration}", duration = stopwatch);
@@ -110,6 +119,30 @@
 //! these states are defined on the type level, invalid method calls (e.g. getting a lap from a stopped stopwatch) are
 //! recognized during compilation instead of at run time.
 //!
+//! The state markers `Initialized`, `Running`, `Paused`, and `Stopped` are exported, so a stopwatch in a specific state
+//! can be passed between functions or stored in a struct field:
+//!
+//! ```
+//! extern crate fine_grained;
+//!
+//! use fine_grained::Running;
+//! use fine_grained::Stopped;
+//! use fine_grained::Stopwatch;
+//! #
+//! # fn do_something() {}
+//!
+//! fn finish(mut stopwatch: Stopwatch<Running>) -> Stopwatch<Stopped> {
+//!     do_something();
+//!     stopwatch.lap();
+//!     stopwatch.stop()
+//! }
+//!
+//! fn main() {
+//!     let stopwatch: Stopwatch<Stopped> = finish(Stopwatch::start_new());
+//!     assert_eq!(stopwatch.number_of_laps(), 1);
+//! }
+//! ```
+//!
 //! # Acknowledgements
 //!
 //! Inspired by Chucky Ellison's stopwatch (https://github.com/ellisonch/rust-stopwatch).
@@ -118,19 +151,40 @@
         missing_debug_implementations, missing_copy_implementations,
         trivial_casts, trivial_numeric_casts,
         unused_extern_crates, unused_import_braces, unused_qualifications, unused_results)]
-#![cfg_attr(feature = "cargo-clippy", allow(inline_always))]
-#![cfg_attr(feature = "cargo-clippy", warn(empty_enum, enum_glob_use, if_not_else, items_after_statements,
-                                           missing_docs_in_private_items, nonminimal_bool, option_unwrap_used,
-                                           pub_enum_variant_names, print_stdout, result_unwrap_used, similar_names,
-                                           single_match_else, stutter, used_underscore_binding, use_debug,
-                                           wrong_self_convention, wrong_pub_self_convention))]
+#![allow(clippy::inline_always)]
+#![warn(clippy::empty_enums, clippy::enum_glob_use, clippy::if_not_else, clippy::items_after_statements,
+        clippy::missing_docs_in_private_items, clippy::nonminimal_bool, clippy::unwrap_used, clippy::print_stdout,
+        clippy::similar_names, clippy::single_match_else, clippy::module_name_repetitions,
+        clippy::used_underscore_binding, clippy::use_debug, clippy::wrong_self_convention)]
 
-extern crate time;
+#[cfg(feature = "quanta")]
+extern crate quanta;
+#[cfg(feature = "rustfft")]
+extern crate rustfft;
 
+pub use self::laps::Laps;
+#[cfg(feature = "threading")]
+pub use self::reporter::ReporterHandle;
+#[cfg(feature = "threading")]
+pub use self::reporter::StopwatchReport;
+pub use self::statistics::LapStatistics;
 pub use self::stopwatch::Stopwatch;
+pub use self::stopwatch::RecordingMode;
+pub use self::stopwatch::CheckpointGuard;
 pub use self::stopwatch::Initialized;
 pub use self::stopwatch::Running;
 pub use self::stopwatch::Paused;
 pub use self::stopwatch::Stopped;
+pub use self::stopwatch::SuspendGuard;
+pub use self::stopwatch_group::StopwatchGroup;
+pub use self::tagged_stopwatch::TaggedStopwatch;
+pub use self::time_unit::TimeUnit;
 
+mod laps;
+#[cfg(feature = "threading")]
+mod reporter;
+mod statistics;
 mod stopwatch;
+mod stopwatch_group;
+mod tagged_stopwatch;
+mod time_unit;
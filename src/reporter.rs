@@ -0,0 +1,149 @@
+// Copyright 2017 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Periodic reports of a shared running stopwatch on a background thread.
+
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use statistics::LapStatistics;
+use stopwatch::Running;
+use stopwatch::Stopwatch;
+
+/// A snapshot of a shared running stopwatch, taken by a reporter thread (see
+/// [`Stopwatch::spawn_reporter()`](struct.Stopwatch.html#method.spawn_reporter)). All times are in nanoseconds.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StopwatchReport {
+    /// The total time the stopwatch has been running, including the current lap up to the snapshot.
+    pub elapsed: u64,
+
+    /// The duration of the current, not yet finished lap up to the snapshot.
+    pub current_lap: u64,
+
+    /// The statistics of all finished laps.
+    pub statistics: LapStatistics,
+
+    /// The notes attached to the stopwatch (see [`Stopwatch::annotate()`](struct.Stopwatch.html#method.annotate)).
+    pub notes: Vec<String>,
+
+    /// The epoch label of the stopwatch, if one has been set (see
+    /// [`Stopwatch::set_epoch_label()`](struct.Stopwatch.html#method.set_epoch_label)).
+    pub epoch_label: Option<String>,
+}
+
+impl StopwatchReport {
+    /// Take a snapshot of the given `stopwatch`.
+    fn of(stopwatch: &Stopwatch<Running>) -> StopwatchReport {
+        // Read the current lap first, so it never exceeds the elapsed time read afterwards.
+        let current_lap: u64 = stopwatch.get_current_laps_duration();
+        StopwatchReport {
+            elapsed: stopwatch.total_time(),
+            current_lap,
+            statistics: stopwatch.statistics(),
+            notes: stopwatch.notes().to_vec(),
+            epoch_label: stopwatch.epoch_label().map(String::from),
+        }
+    }
+}
+
+/// A handle to a reporter thread started by
+/// [`Stopwatch::spawn_reporter()`](struct.Stopwatch.html#method.spawn_reporter).
+///
+/// Dropping the handle stops the reporter and waits for its thread to finish.
+#[derive(Debug)]
+pub struct ReporterHandle {
+    /// The sender whose disconnection tells the reporter thread to stop.
+    stop: Option<Sender<()>>,
+
+    /// The reporter thread.
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for ReporterHandle {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Stopwatch<Running> {
+    /// Call `f` every `interval` on a background thread with a [snapshot](struct.StopwatchReport.html) of the shared
+    /// `stopwatch` at that moment, e.g. to report the progress of a long job without polling.
+    ///
+    /// Since the reporter thread reads the stopwatch while the caller keeps measuring laps, the stopwatch cannot be
+    /// borrowed via `&self`, but must be shared behind an `Arc<Mutex<_>>`. The stopwatch is locked only while taking
+    /// the snapshot. The reporter stops when the returned handle is dropped.
+    pub fn spawn_reporter<F: Fn(StopwatchReport) + Send + 'static>(stopwatch: &Arc<Mutex<Stopwatch<Running>>>,
+                                                                    interval: Duration, f: F) -> ReporterHandle {
+        let stopwatch: Arc<Mutex<Stopwatch<Running>>> = Arc::clone(stopwatch);
+        let (stop, stopped): (Sender<()>, Receiver<()>) = mpsc::channel();
+        let thread: JoinHandle<()> = thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                let snapshot: StopwatchReport = StopwatchReport::of(&stopwatch.lock()
+                    .unwrap_or_else(PoisonError::into_inner));
+                f(snapshot);
+            }
+        });
+
+        ReporterHandle {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_results)]
+    #![allow(clippy::unwrap_used)]
+
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::thread;
+    use std::time::Duration;
+
+    use stopwatch::Stopwatch;
+    use super::StopwatchReport;
+
+    #[test]
+    fn spawn_reporter() {
+        let stopwatch = Arc::new(Mutex::new(Stopwatch::start_new()));
+        stopwatch.lock().unwrap().lap();
+        stopwatch.lock().unwrap().annotate("ran on battery");
+        stopwatch.lock().unwrap().set_epoch_label("request-42");
+        let reports: Arc<Mutex<Vec<StopwatchReport>>> = Arc::new(Mutex::new(Vec::new()));
+        let reports_in_callback = Arc::clone(&reports);
+        let handle = Stopwatch::spawn_reporter(&stopwatch, Duration::from_millis(50), move |report| {
+            reports_in_callback.lock().unwrap().push(report);
+        });
+
+        // Only the lower bound is deterministic: the reporter thread may be scheduled late, but never early.
+        thread::sleep(Duration::from_millis(250));
+        drop(handle);
+        let number_of_reports: usize = reports.lock().unwrap().len();
+        assert!(number_of_reports >= 1, "{} reports", number_of_reports);
+
+        let last_report: StopwatchReport = reports.lock().unwrap().last().unwrap().clone();
+        assert_eq!(last_report.statistics.count, 1);
+        assert!(last_report.current_lap >= 50_000_000);
+        assert!(last_report.elapsed >= last_report.statistics.total + last_report.current_lap);
+        assert_eq!(last_report.notes, vec![String::from("ran on battery")]);
+        assert_eq!(last_report.epoch_label.as_deref(), Some("request-42"));
+
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(reports.lock().unwrap().len(), number_of_reports);
+    }
+}
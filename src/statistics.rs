@@ -0,0 +1,402 @@
+// Copyright 2017 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Statistical helper functions on lap times.
+
+use std::fmt;
+
+#[cfg(feature = "rustfft")]
+use rustfft::FftPlanner;
+#[cfg(feature = "rustfft")]
+use rustfft::num_complex::Complex;
+use time_unit;
+
+/// Descriptive statistics of a set of lap times, all of them in nanoseconds.
+///
+/// See [`Stopwatch::statistics()`](struct.Stopwatch.html#method.statistics) for details.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LapStatistics {
+    /// The number of laps.
+    pub count: usize,
+
+    /// The sum of all laps.
+    pub total: u64,
+
+    /// The shortest lap.
+    pub min: u64,
+
+    /// The longest lap.
+    pub max: u64,
+
+    /// The arithmetic mean of all laps.
+    pub mean: f64,
+
+    /// The median lap, i.e. the 50th percentile.
+    pub median: u64,
+
+    /// The population standard deviation of all laps.
+    pub std_dev: f64,
+
+    /// The 90th percentile of all laps.
+    pub p90: u64,
+
+    /// The 99th percentile of all laps.
+    pub p99: u64,
+}
+
+impl LapStatistics {
+    /// Compute the statistics of the given laps. If there are no laps, all values are `0`.
+    pub(crate) fn from_laps(laps: &[u64]) -> LapStatistics {
+        let mut sorted_laps: Vec<u64> = laps.to_vec();
+        sorted_laps.sort();
+
+        LapStatistics {
+            count: sorted_laps.len(),
+            total: sorted_laps.iter().sum(),
+            min: sorted_laps.first().copied().unwrap_or(0),
+            max: sorted_laps.last().copied().unwrap_or(0),
+            mean: mean(&sorted_laps).unwrap_or(0.0),
+            median: percentile(&sorted_laps, 50.0).unwrap_or(0),
+            std_dev: standard_deviation(&sorted_laps).unwrap_or(0.0),
+            p90: percentile(&sorted_laps, 90.0).unwrap_or(0),
+            p99: percentile(&sorted_laps, 99.0).unwrap_or(0),
+        }
+    }
+
+    /// Compute the statistics known for `count` laps of which only the `total` and the longest lap `max` have been
+    /// tracked. All other values are `0`.
+    pub(crate) fn from_peak(count: usize, total: u64, max: u64) -> LapStatistics {
+        LapStatistics {
+            count,
+            total,
+            max,
+            mean: if count == 0 { 0.0 } else { total as f64 / count as f64 },
+            ..LapStatistics::default()
+        }
+    }
+}
+
+impl fmt::Display for LapStatistics {
+    /// Format the statistics as a single line, e.g.
+    /// `3 laps, total 600ns, min 100ns, max 300ns, mean 200ns, median 200ns, std dev 82ns, p90 300ns, p99 300ns`.
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{count} {laps}, total {total}",
+               count = self.count,
+               laps = if self.count == 1 { "lap" } else { "laps" },
+               total = time_unit::format_nanoseconds(self.total as f64))?;
+
+        if self.count == 0 {
+            return Ok(());
+        }
+
+        write!(formatter, ", min {min}, max {max}, mean {mean}, median {median}, std dev {std_dev}, p90 {p90}, p99 {p99}",
+               min = time_unit::format_nanoseconds(self.min as f64),
+               max = time_unit::format_nanoseconds(self.max as f64),
+               mean = time_unit::format_nanoseconds(self.mean),
+               median = time_unit::format_nanoseconds(self.median as f64),
+               std_dev = time_unit::format_nanoseconds(self.std_dev),
+               p90 = time_unit::format_nanoseconds(self.p90 as f64),
+               p99 = time_unit::format_nanoseconds(self.p99 as f64))
+    }
+}
+
+/// Get the arithmetic mean of the given laps, or `None` if there are no laps.
+pub(crate) fn mean(laps: &[u64]) -> Option<f64> {
+    if laps.is_empty() {
+        return None;
+    }
+
+    let sum: f64 = laps.iter().map(|&lap| lap as f64).sum();
+    Some(sum / laps.len() as f64)
+}
+
+/// Get the population standard deviation of the given laps, or `None` if there are no laps.
+pub(crate) fn standard_deviation(laps: &[u64]) -> Option<f64> {
+    let mean: f64 = mean(laps)?;
+    let squared_deviations: f64 = laps.iter()
+        .map(|&lap| (lap as f64 - mean).powi(2))
+        .sum();
+    Some((squared_deviations / laps.len() as f64).sqrt())
+}
+
+/// Get the `p`-th percentile of the given laps, which must be sorted in ascending order, or `None` if there are no
+/// laps.
+///
+/// The percentile is determined using the nearest-rank method, i.e. it is the smallest lap such that at least `p`
+/// percent of all laps are less than or equal to it.
+///
+/// # Panics
+///
+/// Panics if `p` is not in the range `[0.0, 100.0]`.
+pub(crate) fn percentile(sorted_laps: &[u64], p: f64) -> Option<u64> {
+    assert!((0.0..=100.0).contains(&p), "The percentile must be in the range [0.0, 100.0].");
+
+    if sorted_laps.is_empty() {
+        return None;
+    }
+
+    let rank: usize = (p / 100.0 * sorted_laps.len() as f64).ceil() as usize;
+    Some(sorted_laps[rank.max(1).min(sorted_laps.len()) - 1])
+}
+
+/// Fit the model `lap = a * exp(-b * index) + c` to the given laps by least squares, and return `(a, b, c)`, or `None`
+/// if there are fewer than three laps.
+///
+/// For a fixed decay rate `b`, the best `a` and `c` follow from a linear regression. The decay rate is first searched on a
+/// logarithmic grid from `0.001` to `10` per lap, and then refined by a golden-section search around the best grid
+/// point.
+pub(crate) fn fit_exponential_decay(laps: &[u64]) -> Option<(f64, f64, f64)> {
+    if laps.len() < 3 {
+        return None;
+    }
+
+    let grid: Vec<f64> = (0..=200).map(|step| 10.0_f64.powf(-3.0 + f64::from(step) * 4.0 / 200.0)).collect();
+    let best_step: usize = (0..grid.len())
+        .min_by(|&i, &j| {
+            let (_, _, error_i) = fit_exponential_decay_with_rate(laps, grid[i]);
+            let (_, _, error_j) = fit_exponential_decay_with_rate(laps, grid[j]);
+            error_i.total_cmp(&error_j)
+        })?;
+
+    // Refine the decay rate between the neighbors of the best grid point.
+    let golden_ratio: f64 = (5.0_f64.sqrt() - 1.0) / 2.0;
+    let mut low: f64 = grid[best_step.saturating_sub(1)];
+    let mut high: f64 = grid[(best_step + 1).min(grid.len() - 1)];
+    for _ in 0..100 {
+        let lower_probe: f64 = high - golden_ratio * (high - low);
+        let upper_probe: f64 = low + golden_ratio * (high - low);
+        if fit_exponential_decay_with_rate(laps, lower_probe).2 < fit_exponential_decay_with_rate(laps, upper_probe).2 {
+            high = upper_probe;
+        } else {
+            low = lower_probe;
+        }
+    }
+
+    let rate: f64 = (low + high) / 2.0;
+    let (scale, offset, _) = fit_exponential_decay_with_rate(laps, rate);
+    if !scale.is_finite() || !offset.is_finite() {
+        return None;
+    }
+    Some((scale, rate, offset))
+}
+
+/// Fit the model `lap = a * exp(-rate * index) + c` to the given laps by least squares for the fixed `rate`, and return
+/// `(a, c, sum_of_squared_errors)`.
+fn fit_exponential_decay_with_rate(laps: &[u64], rate: f64) -> (f64, f64, f64) {
+    let n: f64 = laps.len() as f64;
+    let decays: Vec<f64> = (0..laps.len()).map(|index| (-rate * index as f64).exp()).collect();
+    let mean_decay: f64 = decays.iter().sum::<f64>() / n;
+    let mean_lap: f64 = laps.iter().map(|&lap| lap as f64).sum::<f64>() / n;
+
+    let (covariance, variance): (f64, f64) = decays.iter()
+        .zip(laps)
+        .map(|(&decay, &lap)| ((decay - mean_decay) * (lap as f64 - mean_lap), (decay - mean_decay).powi(2)))
+        .fold((0.0, 0.0), |(covariance, variance), (x, y)| (covariance + x, variance + y));
+    let scale: f64 = if variance > 0.0 { covariance / variance } else { 0.0 };
+    let offset: f64 = mean_lap - scale * mean_decay;
+
+    let error: f64 = decays.iter()
+        .zip(laps)
+        .map(|(&decay, &lap)| (scale * decay + offset - lap as f64).powi(2))
+        .sum();
+    (scale, offset, error)
+}
+
+/// Get the two-sided p-value of a Mann–Whitney U test of the laps `a` and `b`, or `None` if either of them has fewer than
+/// eight laps.
+///
+/// The p-value is computed from the normal approximation of the U statistic, with a correction for ties and for
+/// continuity. If all laps are equal, the p-value is `1`.
+pub(crate) fn mann_whitney_p_value(a: &[u64], b: &[u64]) -> Option<f64> {
+    if a.len() < 8 || b.len() < 8 {
+        return None;
+    }
+
+    // Rank all laps together, marking those of `a`. Tied laps get the mean of their ranks.
+    let mut laps: Vec<(u64, bool)> = a.iter().map(|&lap| (lap, true))
+        .chain(b.iter().map(|&lap| (lap, false)))
+        .collect();
+    laps.sort();
+
+    let n: f64 = laps.len() as f64;
+    let mut rank_sum_a: f64 = 0.0;
+    let mut tie_correction: f64 = 0.0;
+    let mut start: usize = 0;
+    while start < laps.len() {
+        let end: usize = start + laps[start..].iter().take_while(|&&(lap, _)| lap == laps[start].0).count();
+        let ties: f64 = (end - start) as f64;
+        let rank: f64 = (start + end + 1) as f64 / 2.0;
+        rank_sum_a += rank * laps[start..end].iter().filter(|&&(_, is_a)| is_a).count() as f64;
+        tie_correction += ties.powi(3) - ties;
+        start = end;
+    }
+
+    let n_a: f64 = a.len() as f64;
+    let n_b: f64 = b.len() as f64;
+    let u: f64 = rank_sum_a - n_a * (n_a + 1.0) / 2.0;
+    let mean: f64 = n_a * n_b / 2.0;
+    let variance: f64 = n_a * n_b / 12.0 * ((n + 1.0) - tie_correction / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        return Some(1.0);
+    }
+
+    let z: f64 = ((u - mean).abs() - 0.5).max(0.0) / variance.sqrt();
+    Some(complementary_error_function(z / 2.0_f64.sqrt()).min(1.0))
+}
+
+/// Get the period (in laps) of the strongest non-constant frequency component of the lap sequence, or `None` if there
+/// are fewer than four laps or no component stands out.
+///
+/// A component stands out if its power is at least eight times the average power of all non-constant components. Since
+/// spiky patterns have strong harmonics, the fundamental frequency of the strongest component is chosen instead if it
+/// has at least half of the strongest component's power.
+#[cfg(feature = "rustfft")]
+pub(crate) fn dominant_period(laps: &[u64]) -> Option<usize> {
+    if laps.len() < 4 {
+        return None;
+    }
+
+    let mean: f64 = mean(laps)?;
+    let mut spectrum: Vec<Complex<f64>> = laps.iter()
+        .map(|&lap| Complex::new(lap as f64 - mean, 0.0))
+        .collect();
+    FftPlanner::new().plan_fft_forward(laps.len()).process(&mut spectrum);
+
+    // The spectrum of a real sequence is symmetric, so only the first half is relevant. Index 0 is the constant part.
+    let powers: Vec<f64> = spectrum[1..=laps.len() / 2].iter()
+        .map(Complex::norm_sqr)
+        .collect();
+    let average_power: f64 = powers.iter().sum::<f64>() / powers.len() as f64;
+    let (strongest_frequency, strongest_power) = powers.iter()
+        .enumerate()
+        .map(|(index, &power)| (index + 1, power))
+        .fold((0, 0.0), |best, current| if current.1 > best.1 { current } else { best });
+    if strongest_power <= 0.0 || strongest_power < 8.0 * average_power {
+        return None;
+    }
+
+    let frequency: usize = (1..strongest_frequency)
+        .find(|&frequency| strongest_frequency % frequency == 0 && powers[frequency - 1] >= strongest_power / 2.0)
+        .unwrap_or(strongest_frequency);
+    Some((laps.len() as f64 / frequency as f64).round() as usize)
+}
+
+/// Get an approximation of the complementary error function at `x >= 0`, with an absolute error below `1.5e-7`.
+///
+/// See Abramowitz and Stegun, Handbook of Mathematical Functions, formula 7.1.26.
+fn complementary_error_function(x: f64) -> f64 {
+    let t: f64 = 1.0 / (1.0 + 0.327_591_1 * x);
+    let polynomial: f64 = t * (0.254_829_592
+        + t * (-0.284_496_736 + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    polynomial * (-x * x).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_results)]
+
+    use super::LapStatistics;
+
+    #[test]
+    fn lap_statistics() {
+        assert_eq!(LapStatistics::from_laps(&[]), LapStatistics::default());
+
+        let statistics = LapStatistics::from_laps(&[300, 100, 200]);
+        assert_eq!(statistics.count, 3);
+        assert_eq!(statistics.total, 600);
+        assert_eq!(statistics.min, 100);
+        assert_eq!(statistics.max, 300);
+        assert_eq!(statistics.mean, 200.0);
+        assert_eq!(statistics.median, 200);
+        assert!((statistics.std_dev - 81.65).abs() < 0.01);
+        assert_eq!(statistics.p90, 300);
+        assert_eq!(statistics.p99, 300);
+    }
+
+    #[test]
+    fn lap_statistics_fmt_display() {
+        assert_eq!(format!("{}", LapStatistics::from_laps(&[])), "0 laps, total 0ns");
+        assert_eq!(format!("{}", LapStatistics::from_laps(&[300, 100, 200])),
+                   "3 laps, total 600ns, min 100ns, max 300ns, mean 200ns, median 200ns, std dev 82ns, p90 300ns, \
+                    p99 300ns");
+    }
+
+    #[test]
+    fn mean() {
+        assert_eq!(super::mean(&[]), None);
+        assert_eq!(super::mean(&[100, 200, 600]), Some(300.0));
+    }
+
+    #[test]
+    fn standard_deviation() {
+        assert_eq!(super::standard_deviation(&[]), None);
+        assert_eq!(super::standard_deviation(&[100, 100]), Some(0.0));
+        assert_eq!(super::standard_deviation(&[2, 4, 4, 4, 5, 5, 7, 9]), Some(2.0));
+    }
+
+    #[test]
+    fn percentile() {
+        assert_eq!(super::percentile(&[], 50.0), None);
+
+        let laps: Vec<u64> = (1..11).map(|i| i * 100).collect();
+        assert_eq!(super::percentile(&laps, 0.0), Some(100));
+        assert_eq!(super::percentile(&laps, 50.0), Some(500));
+        assert_eq!(super::percentile(&laps, 90.0), Some(900));
+        assert_eq!(super::percentile(&laps, 99.0), Some(1_000));
+        assert_eq!(super::percentile(&laps, 100.0), Some(1_000));
+    }
+
+    #[test]
+    fn fit_exponential_decay() {
+        assert_eq!(super::fit_exponential_decay(&[100, 50]), None);
+
+        let laps: Vec<u64> = (0..30).map(|index| (4_000.0 * (-0.3 * f64::from(index)).exp() + 1_000.0) as u64).collect();
+        let (scale, rate, offset) = super::fit_exponential_decay(&laps).unwrap_or((0.0, 0.0, 0.0));
+        assert!((scale - 4_000.0).abs() < 10.0);
+        assert!((rate - 0.3).abs() < 0.01);
+        assert!((offset - 1_000.0).abs() < 5.0);
+    }
+
+    #[cfg(feature = "rustfft")]
+    #[test]
+    fn dominant_period() {
+        assert_eq!(super::dominant_period(&[100, 500, 100]), None);
+        assert_eq!(super::dominant_period(&[100; 20]), None);
+
+        let laps: Vec<u64> = (0..50).map(|index| if index % 5 == 4 { 5_000 } else { 1_000 + index * 7 % 30 }).collect();
+        assert_eq!(super::dominant_period(&laps), Some(5));
+
+        let laps: Vec<u64> = (0..64).map(|index| 1_000 + (index * index * 7_919 + index * 31) % 1_009).collect();
+        assert_eq!(super::dominant_period(&laps), None);
+    }
+
+    #[test]
+    fn mann_whitney_p_value() {
+        let a: Vec<u64> = (1..11).collect();
+        assert_eq!(super::mann_whitney_p_value(&a, &a[..7]), None);
+        assert_eq!(super::mann_whitney_p_value(&[100; 10], &[100; 10]), Some(1.0));
+
+        let p_value: f64 = super::mann_whitney_p_value(&a, &a).unwrap_or(0.0);
+        assert!(p_value > 0.95);
+
+        let b: Vec<u64> = (11..21).collect();
+        let p_value: f64 = super::mann_whitney_p_value(&a, &b).unwrap_or(1.0);
+        assert!(p_value < 0.001);
+    }
+
+    #[test]
+    fn complementary_error_function() {
+        assert!((super::complementary_error_function(0.0) - 1.0).abs() < 1e-6);
+        assert!((super::complementary_error_function(1.0) - 0.157_299_2).abs() < 1e-6);
+        assert!(super::complementary_error_function(10.0) < 1e-6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn percentile_invalid() {
+        super::percentile(&[100], 100.5);
+    }
+}
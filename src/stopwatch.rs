@@ -8,10 +8,31 @@
 //!
 //! See the [crate documentation](../index.html) for examples.
 
+use std::any;
+use std::collections::HashMap;
 use std::fmt;
+use std::hash::Hash;
+use std::io;
+use std::iter;
 use std::marker::PhantomData;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::sync::PoisonError;
+use std::sync::mpsc::Sender;
+use std::sync::mpsc::SyncSender;
+use std::time::Duration;
+use std::time::Instant;
 
-use time;
+#[cfg(feature = "quanta")]
+use quanta;
+
+use laps::Laps;
+use statistics;
+use statistics::LapStatistics;
+use time_unit;
+use time_unit::TimeUnit;
 
 /// A unit-like struct for marking a stopwatch as initialized.
 #[derive(Clone, Copy, Debug)]
@@ -29,6 +50,18 @@ pub struct Paused;
 #[derive(Clone, Copy, Debug)]
 pub struct Stopped;
 
+/// How [`Stopwatch::lap()`](struct.Stopwatch.html#method.lap) records the time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RecordingMode {
+    /// Finish the current lap and start a new one, i.e. record the time since the previous lap.
+    #[default]
+    Interval,
+
+    /// Save the current total time without starting a new lap, i.e. record the time since the start. The totals are
+    /// available via [`Stopwatch::cumulative_laps()`](struct.Stopwatch.html#method.cumulative_laps).
+    Cumulative,
+}
+
 /// A stopwatch with lap functionality and nanosecond resolution.
 ///
 /// See the [crate documentation](index.html) for examples.
@@ -38,317 +71,3676 @@ pub struct Stopwatch<State> {
     laps: Vec<u64>,
 
     /// The start time of the currently running lap, or `None` if the stopwatch is not running.
-    start_time: Option<u64>,
+    start_time: Option<Instant>,
+
+    /// The duration of the current lap up to the time the stopwatch has been paused, or `None` if the stopwatch is not
+    /// paused or no lap has been in progress when it was paused.
+    paused_lap: Option<u64>,
 
     /// The sum of all finished laps.
     total_time: u64,
 
+    /// Free-form notes attached to the stopwatch.
+    notes: Vec<String>,
+
+    /// Named marks and checkpoints with the total time at which they were set.
+    marks: Vec<(String, u64)>,
+
+    /// A label shared by multiple stopwatches to correlate their measurements.
+    epoch_label: Option<String>,
+
+    /// The names of named laps, with the index of the respective lap.
+    lap_names: Vec<(usize, String)>,
+
+    /// The prefix used to automatically name laps, together with the number of the next automatically named lap.
+    auto_name_prefix: Option<(String, usize)>,
+
+    /// The indices of laps that have been committed before they were complete.
+    partial_laps: Vec<usize>,
+
+    /// The multiple of the median lap time above which new laps are flagged as suspicious, or `None` if laps are not
+    /// checked.
+    jitter_guard: Option<f64>,
+
+    /// The indices of laps that have been flagged as suspicious.
+    suspicious_laps: Vec<usize>,
+
+    /// A callback to be fired once the total time exceeds a threshold.
+    total_alarm: TotalAlarm,
+
+    /// The sequence number of the next saved lap, which keeps increasing across resets.
+    next_sequence_id: u64,
+
+    /// The last saved lap, even if it has only been tracked instead of stored, or `None` if no lap has been saved yet.
+    last_saved_lap: Option<u64>,
+
+    /// The state of the adaptive sampling of laps, or `None` if laps are not sampled adaptively.
+    adaptive_sampling: Option<AdaptiveSampling>,
+
+    /// Whether laps with a duration of `0` are discarded instead of saved.
+    drop_zero_laps: bool,
+
+    /// The number of times the stopwatch has been paused.
+    number_of_pauses: usize,
+
+    /// The longest lap and the number of laps if only the longest lap is tracked instead of all laps, or `None` if all
+    /// laps are stored.
+    peak_lap: Option<(u64, usize)>,
+
+    /// How `lap()` records the time.
+    recording_mode: RecordingMode,
+
+    /// The total times at which `lap()` has been called in the cumulative recording mode.
+    cumulative_laps: Vec<u64>,
+
+    /// The start time of the current lap if it has been started by `lap_precise()`, together with the same point in
+    /// time on the high-resolution clock.
+    #[cfg(feature = "quanta")]
+    precise_start_time: Option<(Instant, quanta::Instant)>,
+
     /// The state of the stopwatch.
     state: PhantomData<State>,
 }
 
 impl<State> Stopwatch<State> {
-    /// Determine if the stopwatch is currently running.
-    pub fn is_running(&self) -> bool {
-        self.start_time.is_some()
+    /// Attach a free-form note to the stopwatch, e.g. describing the circumstances of the measurement.
+    ///
+    /// The notes are included in the output of [`to_json()`](#method.to_json), and in the snapshots taken by
+    /// [`spawn_reporter()`](#method.spawn_reporter) if the `threading` feature is enabled.
+    pub fn annotate<N: Into<String>>(&mut self, note: N) {
+        self.notes.push(note.into());
     }
 
-    // TODO: Implement `IntoIterator` instead for iterating over the laps.
-    /// Get the list of all measured lap times in the order the laps were timed.
-    pub fn laps(&self) -> &Vec<u64> {
-        &self.laps
-    }
+    /// Get the laps as parallel columns of their indices, durations, and cumulative times.
+    ///
+    /// The `i`-th element of each column describes the `i`-th lap, which maps directly onto columnar data formats.
+    pub fn columns(&self) -> (Vec<usize>, Vec<u64>, Vec<u64>) {
+        let indices: Vec<usize> = (0..self.laps.len()).collect();
+        let cumulative: Vec<u64> = self.laps.iter()
+            .scan(0, |total, &lap| {
+                *total += lap;
+                Some(*total)
+            })
+            .collect();
 
-    /// Get the number of measured laps.
-    pub fn number_of_laps(&self) -> usize {
-        self.laps.len()
+        (indices, self.laps.clone(), cumulative)
     }
 
-    /// Get the total time the stopwatch has been running.
+    /// Get the total time at which the checkpoint (or mark) with the given `name` has been saved.
     ///
-    /// If the stopwatch is still running, the total time is the time from starting the
-    /// stopwatch until now. Otherwise, it is the sum of all laps.
-    pub fn total_time(&self) -> u64 {
-        match self.start_time {
-            Some(current_lap_start_time) => {
-                // If the stopwatch is currently running, the total time is the saved total time plus the current lap's
-                // duration up to this point.
-                let current_time: u64 = time::precise_time_ns();
-                let lap: u64 = current_time - current_lap_start_time;
-                self.total_time + lap
-            },
-            None => self.total_time
-        }
+    /// Returns `None` if there is no such checkpoint.
+    pub fn checkpoint(&self, name: &str) -> Option<u64> {
+        self.marks.iter()
+            .rev()
+            .find(|&(checkpoint, _)| checkpoint == name)
+            .map(|&(_, total_time)| total_time)
     }
-}
 
-impl Stopwatch<Initialized> {
-    /// Initialize a new stopwatch without starting it.
-    pub fn new() -> Stopwatch<Initialized> {
-        Stopwatch {
-            laps: Vec::new(),
-            start_time: None,
-            total_time: 0,
-            state: PhantomData::<Initialized>,
-        }
+    /// Get the total times saved by [`lap()`](#method.lap) in the
+    /// [cumulative recording mode](enum.RecordingMode.html#variant.Cumulative), in the order they were saved.
+    pub fn cumulative_laps(&self) -> &[u64] {
+        &self.cumulative_laps
     }
 
-    /// Start the stopwatch.
-    pub fn start(self) -> Stopwatch<Running> {
-        Stopwatch {
-            laps: self.laps,
-            start_time: Some(time::precise_time_ns()),
-            total_time: self.total_time,
-            state: PhantomData::<Running>,
+    /// Get the number of laps that took longer than the `p`-th percentile lap time, e.g. for SLA reporting.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is not in the range `[0.0, 100.0]`.
+    pub fn count_above_percentile(&self, p: f64) -> usize {
+        let mut laps: Vec<u64> = self.laps.clone();
+        laps.sort();
+
+        match statistics::percentile(&laps, p) {
+            Some(percentile) => laps.iter().filter(|&&lap| lap > percentile).count(),
+            None => 0
         }
     }
-}
 
-impl Stopwatch<Running> {
-    /// Initialize a new stopwatch and start it.
+    /// Get the cumulative time at which the lap with the given `index` finished, i.e. the sum of the laps `0..=index`.
     ///
-    /// This an alias for [`Stopwatch::new()`](#method.new)[`.start()`](#method.start)
-    pub fn start_new() -> Stopwatch<Running> {
-        Stopwatch::new().start()
-    }
+    /// Returns `None` if there is no lap with the given `index`.
+    pub fn cumulative_at(&self, index: usize) -> Option<u64> {
+        if index >= self.laps.len() {
+            return None;
+        }
 
-    /// Start a new lap. Save the last lap's time and return it.
-    pub fn lap(&mut self) -> u64 {
-        let lap: u64 = self.finish_current_lap();
-        self.start_time = Some(time::precise_time_ns());
-        lap
+        Some(self.laps[..=index].iter().sum())
     }
 
-    /// Finish the current lap and immediately pause the stopwatch.
-    pub fn lap_and_pause(mut self) -> (u64, Stopwatch<Paused>) {
-        let lap: u64 = self.finish_current_lap();
+    /// Get the epoch label of the stopwatch, if one has been set.
+    pub fn epoch_label(&self) -> Option<&str> {
+        self.epoch_label.as_deref()
+    }
 
-        // Insert an empty lap into the list. This will be removed on resume.
-        self.laps.push(0);
+    /// Set the epoch label of the stopwatch.
+    ///
+    /// The epoch label is meant to be shared by multiple stopwatches taking part in the same run, so their measurements
+    /// can be correlated. It is kept across state changes, but cleared when the stopwatch is reset. The label is included
+    /// in the output of [`to_json()`](#method.to_json), and in the snapshots taken by
+    /// [`spawn_reporter()`](#method.spawn_reporter) if the `threading` feature is enabled.
+    pub fn set_epoch_label<L: Into<String>>(&mut self, label: L) {
+        self.epoch_label = Some(label.into());
+    }
 
-        let stopwatch = Stopwatch {
-            laps: self.laps,
-            start_time: None,
-            total_time: self.total_time,
-            state: PhantomData::<Paused>,
+    /// Get a multi-line description of the stopwatch's current state for quick inspection, e.g. when debugging.
+    ///
+    /// The description contains the name of the state, the number of laps, the total time, whether the stopwatch is
+    /// running, how often it has been paused, and its [recording mode](#method.recording_mode).
+    pub fn describe(&self) -> String {
+        let recording_mode: &str = match self.recording_mode {
+            RecordingMode::Interval => "interval",
+            RecordingMode::Cumulative => "cumulative",
         };
+        format!("state: {state}\nlaps: {laps}\ntotal time: {total}\nrunning: {running}\npauses: {pauses}\n\
+                 recording mode: {recording_mode}",
+                state = self.state(),
+                laps = self.number_of_laps(),
+                total = time_unit::format_nanoseconds(self.total_time() as f64),
+                running = self.is_running(),
+                pauses = self.number_of_pauses,
+                recording_mode = recording_mode)
+    }
 
-        (lap, stopwatch)
+    /// Get how [`lap()`](#method.lap) records the time.
+    pub fn recording_mode(&self) -> RecordingMode {
+        self.recording_mode
     }
 
-    /// Finish the current lap and immediately stop the stopwatch.
-    pub fn lap_and_stop(mut self) -> (u64, Stopwatch<Stopped>) {
-        let lap: u64 = self.finish_current_lap();
-        (lap, self.stop())
+    /// Get the time of the first lap, e.g. to measure cold-start costs, or `None` if no lap has been measured.
+    pub fn first_lap(&self) -> Option<u64> {
+        self.laps.first().copied()
     }
 
-    /// Pause the stopwatch.
+    /// Group the finished laps by the key `f(index, duration)` computed for each lap.
     ///
-    /// The current lap is inserted into the list of laps with its duration at this time.
-    pub fn pause(mut self) -> Stopwatch<Paused> {
-        // Store how long the current lap has been running so far.
-        let lap: u64 = self.get_current_laps_duration();
-        self.laps.push(lap);
+    /// Within each group, the laps keep the order in which they were timed.
+    pub fn group_laps_by<K: Eq + Hash, F: Fn(usize, u64) -> K>(&self, f: F) -> HashMap<K, Vec<u64>> {
+        let mut groups: HashMap<K, Vec<u64>> = HashMap::new();
+        for (index, &lap) in self.laps.iter().enumerate() {
+            groups.entry(f(index, lap)).or_default().push(lap);
+        }
+        groups
+    }
 
-        Stopwatch {
-            laps: self.laps,
-            start_time: None,
-            total_time: self.total_time,
-            state: PhantomData::<Paused>,
+    /// Count the laps in the buckets defined by the given `edges`, e.g. for SLA tiers.
+    ///
+    /// The `i`-th count is the number of laps in the range `edges[i - 1]..edges[i]`, where the first bucket contains all
+    /// laps shorter than `edges[0]`. An additional last bucket counts all laps of at least the last edge, thus there
+    /// are `edges.len() + 1` counts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `edges` is not sorted in ascending order.
+    pub fn histogram_with_edges(&self, edges: &[u64]) -> Vec<usize> {
+        assert!(edges.windows(2).all(|pair| pair[0] <= pair[1]), "The bucket edges must be sorted in ascending order.");
+
+        let mut counts: Vec<usize> = vec![0; edges.len() + 1];
+        for &lap in &self.laps {
+            counts[edges.partition_point(|&edge| edge <= lap)] += 1;
         }
+        counts
     }
 
-    /// Stop the stopwatch.
-    pub fn stop(self) -> Stopwatch<Stopped> {
-        Stopwatch {
-            laps: self.laps,
-            start_time: None,
-            total_time: self.total_time,
-            state: PhantomData::<Stopped>,
+    /// Get by how much the `p`-th percentile lap time has to decrease to not exceed `target_ns` nanoseconds, e.g. for
+    /// capacity planning, or `None` if there are no laps.
+    ///
+    /// If the percentile already meets the target, `0` is returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is not in the range `[0.0, 100.0]`.
+    pub fn improvement_needed_for_percentile(&self, p: f64, target_ns: u64) -> Option<u64> {
+        let mut laps: Vec<u64> = self.laps.clone();
+        laps.sort();
+
+        statistics::percentile(&laps, p).map(|percentile| percentile.saturating_sub(target_ns))
+    }
+
+    /// Determine if the lap times have stabilized, e.g. to decide when to stop collecting benchmark samples.
+    ///
+    /// This is the case if there are at least `min_laps` laps and the coefficient of variation of the laps (i.e. their
+    /// standard deviation divided by their mean) is below `cv_threshold`.
+    pub fn is_converged(&self, cv_threshold: f64, min_laps: usize) -> bool {
+        if self.laps.is_empty() || self.laps.len() < min_laps {
+            return false;
         }
+
+        let mean: f64 = statistics::mean(&self.laps).unwrap_or(0.0);
+        let standard_deviation: f64 = statistics::standard_deviation(&self.laps).unwrap_or(0.0);
+
+        // The mean can only be zero if all laps are zero, i.e. if the laps are perfectly stable.
+        let coefficient_of_variation: f64 = if mean > 0.0 { standard_deviation / mean } else { 0.0 };
+        coefficient_of_variation < cv_threshold
     }
 
-    /// Finish the current lap: get its duration and add it to the list of laps and the total time.
-    #[inline(always)]
-    fn finish_current_lap(&mut self) -> u64 {
-        let lap: u64 = self.get_current_laps_duration();
-        self.total_time += lap;
-        self.laps.push(lap);
-        lap
+    /// Determine if no lap has been finished yet.
+    ///
+    /// If the stopwatch is paused, its paused lap does not count as finished.
+    pub fn is_empty(&self) -> bool {
+        self.number_of_laps() == 0
     }
 
-    /// Get the current lap's duration up to this point..
-    #[inline(always)]
-    fn get_current_laps_duration(&self) -> u64 {
-        // Determine this lap's duration.
-        let current_time: u64 = time::precise_time_ns();
-        match self.start_time {
-            Some(time) => current_time - time,
-            None => unreachable!()
+    /// Determine if the stopwatch is currently running.
+    pub fn is_running(&self) -> bool {
+        self.start_time.is_some()
+    }
+
+    /// Determine if the [total time](#method.total_time) differs from `expected_ns` by at most `tolerance_ns`
+    /// nanoseconds.
+    pub fn is_within(&self, expected_ns: u64, tolerance_ns: u64) -> bool {
+        self.total_time().abs_diff(expected_ns) <= tolerance_ns
+    }
+
+    /// Assert that the [total time](#method.total_time) differs from `expected_ns` by at most `tolerance_ns`
+    /// nanoseconds.
+    ///
+    /// This is a helper for tests. See [`is_within()`](#method.is_within) for a non-panicking version.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total time is not within the tolerance.
+    pub fn assert_within(&self, expected_ns: u64, tolerance_ns: u64) {
+        assert!(self.is_within(expected_ns, tolerance_ns),
+                "The total time of {total}ns is not within {tolerance}ns of {expected}ns.",
+                total = self.total_time(), tolerance = tolerance_ns, expected = expected_ns);
+    }
+
+    /// Get an iterator over all measured lap times in the order the laps were timed.
+    pub fn iter(&self) -> Laps<'_> {
+        Laps::new(&self.laps)
+    }
+
+    /// Get the list of all measured lap times in the order the laps were timed.
+    pub fn laps(&self) -> &Vec<u64> {
+        &self.laps
+    }
+
+    /// Get the measured lap times with runs of equal consecutive laps collapsed into `(lap, run length)` pairs, e.g. to
+    /// compress flat regions for plotting.
+    pub fn laps_dedup_consecutive(&self) -> Vec<(u64, usize)> {
+        let mut runs: Vec<(u64, usize)> = Vec::new();
+        for &lap in &self.laps {
+            match runs.last_mut() {
+                Some((previous, run_length)) if *previous == lap => *run_length += 1,
+                _ => runs.push((lap, 1)),
+            }
         }
+        runs
     }
-}
 
-impl Stopwatch<Paused> {
-    /// Resume the stopwatch.
+    /// Get mutable access to the list of all measured lap times, e.g. to subtract a measured baseline from each lap.
     ///
-    /// If a lap has been paused as well (i.e. [`pause()`](#method.pause) has been called), this lap will be resumed.
-    pub fn resume(mut self) -> Stopwatch<Running> {
-        let paused_lap: u64 = match self.laps.pop() {
-            Some(duration) => duration,
-            None => unreachable!()
-        };
-        Stopwatch {
-            laps: self.laps,
-            // The start time of the paused lap dates back to the current time minus the paused lap's duration.
-            start_time: Some(time::precise_time_ns() - paused_lap),
-            total_time: self.total_time,
-            state: PhantomData::<Running>,
+    /// The total time is not updated automatically: after changing the laps, call
+    /// [`recompute_total()`](#method.recompute_total) to keep it consistent with the laps.
+    pub fn laps_mut(&mut self) -> &mut Vec<u64> {
+        &mut self.laps
+    }
+
+    /// Get each lap's share of the sum of all laps in whole percents, e.g. for a pie chart.
+    ///
+    /// The shares are rounded with the largest remainder method, such that they sum to exactly `100`; ties are broken
+    /// in favor of earlier laps. If there are no laps or they took no time at all, all shares are `0`.
+    pub fn lap_percentages_rounded(&self) -> Vec<u32> {
+        let sum: u128 = self.laps.iter().map(|&lap| u128::from(lap)).sum();
+        if sum == 0 {
+            return vec![0; self.laps.len()];
         }
+
+        let mut percentages: Vec<u32> = Vec::with_capacity(self.laps.len());
+        let mut remainders: Vec<(u128, usize)> = Vec::with_capacity(self.laps.len());
+        for (index, &lap) in self.laps.iter().enumerate() {
+            let share: u128 = u128::from(lap) * 100;
+            percentages.push((share / sum) as u32);
+            remainders.push((share % sum, index));
+        }
+
+        let missing: u32 = 100 - percentages.iter().sum::<u32>();
+        remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        for &(_, index) in remainders.iter().take(missing as usize) {
+            percentages[index] += 1;
+        }
+        percentages
     }
 
-    /// Stop the stopwatch.
+    /// Get the number of laps per second over the last `window` laps, e.g. for adaptive throttling.
     ///
-    /// If a lap has been paused as well, (i.e. [`pause()`](#method.pause) has been called), this lap will be stopped.
-    pub fn stop(mut self) -> Stopwatch<Stopped> {
-        // If the last lap's duration is `0`, there is no paused lap (happens if `lap_and_pause()` has been called).
-        let paused_lap: u64 = match self.laps.pop() {
-            Some(0) => 0,
-            Some(paused_lap) => {
-                self.laps.push(paused_lap);
-                paused_lap
-            },
-            None => unreachable!(),
-        };
-        Stopwatch {
-            laps: self.laps,
-            start_time: None,
-            total_time: self.total_time + paused_lap,
-            state: PhantomData::<Stopped>
+    /// If there are fewer than `window` laps, all laps are used. Returns `0.0` if there are no laps in the window, or if
+    /// they took no time at all.
+    pub fn recent_lap_rate(&self, window: usize) -> f64 {
+        let recent_laps: &[u64] = &self.laps[self.laps.len().saturating_sub(window)..];
+        let duration: u64 = recent_laps.iter().sum();
+        if duration == 0 {
+            return 0.0;
         }
+
+        recent_laps.len() as f64 / (duration as f64 / 1_000_000_000.0)
     }
-}
 
-impl Stopwatch<Stopped> {
-    /// Re-initialize the stopwatch without restarting it.
+    /// Set the total time of all finished laps to the sum of the current list of laps.
     ///
-    /// This is an alias for [`Stopwatch::new()`](#method.new).
-    pub fn reset(self) -> Stopwatch<Initialized> {
-        Stopwatch::new()
+    /// This is necessary after changing the laps via [`laps_mut()`](#method.laps_mut).
+    pub fn recompute_total(&mut self) {
+        self.total_time = self.laps.iter().sum();
     }
 
-    /// Re-initialize the stopwatch and start it.
+    /// Get a raw pointer to the list of all measured lap times (in nanoseconds) and its length, e.g. to pass the laps
+    /// across an FFI boundary without copying them.
     ///
-    /// This is an alias for [`Stopwatch::start_new()`](#method.start_new).
-    pub fn restart(self) -> Stopwatch<Running> {
-        Stopwatch::start_new()
+    /// # Safety
+    ///
+    /// Obtaining the pointer is safe, but dereferencing it is only valid as long as the stopwatch is alive and its laps
+    /// are not modified: any method adding, removing, or changing laps (including state changes of the stopwatch) may
+    /// reallocate the underlying buffer and thus invalidate the pointer. The pointer must never be used to write to the
+    /// laps.
+    pub fn as_nanos_ptr(&self) -> (*const u64, usize) {
+        (self.laps.as_ptr(), self.laps.len())
     }
-}
 
-impl<State> fmt::Display for Stopwatch<State> {
-    /// Formats the total time using the given formatter.
-    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        write!(formatter, "{total_time}ns", total_time = self.total_time())
+    /// Get the list of all measured lap times as durations, each rounded to the nearest multiple of the given `unit`.
+    pub fn laps_as_durations_rounded(&self, unit: TimeUnit) -> Vec<Duration> {
+        self.laps.iter()
+            .map(|&lap| Duration::from_nanos(unit.round(lap)))
+            .collect()
     }
-}
 
-#[cfg(test)]
-mod tests {
-    #![allow(unused_results)]
+    /// Get all overlapping windows of `size` consecutive laps, e.g. for analyzing local behavior.
+    ///
+    /// If there are fewer than `size` laps, no windows are returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is `0`.
+    pub fn lap_windows(&self, size: usize) -> Vec<&[u64]> {
+        assert!(size > 0, "The window size must not be zero.");
 
-    use super::Stopwatch;
+        self.laps.windows(size).collect()
+    }
 
-    #[test]
-    fn new() {
-        let stopwatch = Stopwatch::new();
-        assert_eq!(stopwatch.laps, vec![]);
-        assert_eq!(stopwatch.start_time, None);
-        assert_eq!(stopwatch.total_time, 0);
+    /// Get all notes attached to the stopwatch in the order they were added.
+    pub fn notes(&self) -> &[String] {
+        &self.notes
     }
 
-    #[test]
-    fn start_new() {
-        let stopwatch = Stopwatch::start_new();
-        assert_eq!(stopwatch.laps, vec![]);
-        assert!(stopwatch.start_time.is_some());
-        assert_eq!(stopwatch.total_time, 0);
+    /// Estimate the number of bytes of memory used by the stopwatch, including all heap allocations.
+    pub fn memory_footprint_bytes(&self) -> usize {
+        let notes: usize = self.notes.capacity() * size_of::<String>()
+            + self.notes.iter().map(|note| note.capacity()).sum::<usize>();
+        let marks: usize = self.marks.capacity() * size_of::<(String, u64)>()
+            + self.marks.iter().map(|(label, _)| label.capacity()).sum::<usize>();
+        let epoch_label: usize = self.epoch_label.as_ref().map_or(0, |label| label.capacity());
+        let lap_names: usize = self.lap_names.capacity() * size_of::<(usize, String)>()
+            + self.lap_names.iter().map(|(_, name)| name.capacity()).sum::<usize>();
+        let auto_name_prefix: usize = self.auto_name_prefix.as_ref().map_or(0, |(prefix, _)| prefix.capacity());
+        let partial_laps: usize = self.partial_laps.capacity() * size_of::<usize>();
+        let suspicious_laps: usize = self.suspicious_laps.capacity() * size_of::<usize>();
+        let cumulative_laps: usize = self.cumulative_laps.capacity() * size_of::<u64>();
+
+        size_of::<Self>() + self.laps.capacity() * size_of::<u64>() + notes + marks + epoch_label + lap_names
+            + auto_name_prefix + partial_laps + suspicious_laps + cumulative_laps
     }
 
-    #[test]
-    fn start() {
-        let stopwatch = Stopwatch::new();
-        let stopwatch = stopwatch.start();
-        assert!(stopwatch.start_time.is_some());
+    /// Get the name of the lap with the given `index`, or `None` if the lap does not exist or has no name.
+    pub fn lap_name(&self, index: usize) -> Option<&str> {
+        self.lap_names.iter()
+            .find(|&&(lap_index, _)| lap_index == index)
+            .map(|(_, name)| name.as_str())
     }
 
-    #[test]
-    fn lap() {
-        let mut stopwatch = Stopwatch::start_new();
-        let lap_1: u64 = stopwatch.lap();
-        assert!(lap_1 > 0);
-        assert_eq!(stopwatch.laps.len(), 1);
-        assert_eq!(stopwatch.laps[0], lap_1);
-        assert_eq!(stopwatch.total_time, lap_1);
+    /// Get the least-squares slope of the lap times over the lap indices in nanoseconds per lap, e.g. to detect a
+    /// workload slowing down over time, or `None` if there are fewer than two laps.
+    ///
+    /// A positive slope means the laps are getting slower, a negative one means they are getting faster.
+    pub fn lap_trend_slope(&self) -> Option<f64> {
+        if self.laps.len() < 2 {
+            return None;
+        }
 
-        let lap_2: u64 = stopwatch.lap();
-        assert!(lap_2 > 0);
-        assert_eq!(stopwatch.laps.len(), 2);
-        assert_eq!(stopwatch.laps[0], lap_1);
-        assert_eq!(stopwatch.laps[1], lap_2);
-        assert_eq!(stopwatch.total_time, lap_1 + lap_2);
+        let mean_index: f64 = (self.laps.len() - 1) as f64 / 2.0;
+        let mean_lap: f64 = statistics::mean(&self.laps)?;
+        let (covariance, variance): (f64, f64) = self.laps.iter()
+            .enumerate()
+            .map(|(index, &lap)| {
+                let index_deviation: f64 = index as f64 - mean_index;
+                (index_deviation * (lap as f64 - mean_lap), index_deviation * index_deviation)
+            })
+            .fold((0.0, 0.0), |(covariance, variance), (x, y)| (covariance + x, variance + y));
+        Some(covariance / variance)
     }
 
-    #[test]
-    fn stop() {
-        let mut stopwatch = Stopwatch::start_new();
-        let lap: u64 = stopwatch.lap();
-        let stopwatch = stopwatch.stop();
-        assert!(stopwatch.start_time.is_none());
-        assert_eq!(stopwatch.laps.len(), 1);
-        assert_eq!(stopwatch.total_time, lap);
+    /// Get the time of the longest lap, or `None` if no lap has been measured.
+    ///
+    /// For stopwatches created by [`Stopwatch::peak_tracking()`](#method.peak_tracking), this takes constant time.
+    pub fn max_lap(&self) -> Option<u64> {
+        match self.peak_lap {
+            Some((_, 0)) => None,
+            Some((max_lap, _)) => Some(max_lap),
+            None => self.laps.iter().max().copied()
+        }
+    }
 
-        let mut stopwatch = Stopwatch::start_new();
-        let lap: u64 = stopwatch.lap();
-        let stopwatch = stopwatch.pause();
-        let stopwatch = stopwatch.stop();
-        assert!(stopwatch.start_time.is_none());
-        assert_eq!(stopwatch.laps.len(), 2);
-        assert!(stopwatch.total_time > lap);
+    /// Get the sequence number the next saved lap will get.
+    ///
+    /// All saved laps are numbered consecutively, starting at `0`. Unlike the lap indices, the numbering continues when
+    /// the stopwatch is [reset](#method.reset) or [restarted](#method.restart), so the numbers can be used to identify
+    /// laps across multiple runs, e.g. in merged logs.
+    pub fn next_sequence_id(&self) -> u64 {
+        self.next_sequence_id
+    }
 
-        let stopwatch = Stopwatch::start_new();
-        let (lap, stopwatch) = stopwatch.lap_and_pause();
-        let stopwatch = stopwatch.stop();
-        assert!(stopwatch.start_time.is_none());
-        assert_eq!(stopwatch.laps.len(), 1);
-        assert_eq!(stopwatch.total_time, lap);
+    /// Get the number of measured laps.
+    pub fn number_of_laps(&self) -> usize {
+        match self.peak_lap {
+            Some((_, number_of_laps)) => number_of_laps,
+            None => self.laps.len()
+        }
+    }
+
+    /// Get the indices of all laps committed by [`commit_partial_lap()`](#method.commit_partial_lap), in ascending
+    /// order.
+    pub fn partial_lap_indices(&self) -> &[usize] {
+        &self.partial_laps
+    }
+
+    /// Get the indices of all laps flagged as suspicious by the jitter guard (see
+    /// [`with_jitter_guard()`](#method.with_jitter_guard)), in ascending order.
+    pub fn suspicious_lap_indices(&self) -> &[usize] {
+        &self.suspicious_laps
+    }
+
+    /// Get the trailing moving average of the lap times over `window` laps, for each lap.
+    ///
+    /// The `i`-th value is the mean of the laps `i + 1 - window..=i`. For the first laps, which have fewer than `window`
+    /// laps up to them, the mean of all available laps is used.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is `0`.
+    pub fn rolling_mean(&self, window: usize) -> Vec<f64> {
+        assert!(window > 0, "The window size must not be zero.");
+
+        (0..self.laps.len())
+            .map(|index| {
+                let laps: &[u64] = &self.laps[(index + 1).saturating_sub(window)..=index];
+                laps.iter().sum::<u64>() as f64 / laps.len() as f64
+            })
+            .collect()
+    }
+
+    /// Get descriptive statistics of all laps, computed at once.
+    ///
+    /// If there are no laps, all values are `0`. For stopwatches created by
+    /// [`Stopwatch::peak_tracking()`](#method.peak_tracking), the count is [`number_of_laps()`](#method.number_of_laps)
+    /// and only the count, total, mean, and maximum are known; all other values are `0`.
+    pub fn statistics(&self) -> LapStatistics {
+        match self.peak_lap {
+            Some(_) => LapStatistics::from_peak(self.number_of_laps(), self.total_time, self.max_lap().unwrap_or(0)),
+            None => LapStatistics::from_laps(&self.laps)
+        }
+    }
+
+    /// Get a single-line chart of the lap times made from the Unicode block characters `▁▂▃▄▅▆▇█`, e.g. for terminal
+    /// output.
+    ///
+    /// Each lap is represented by one character, scaled between the shortest lap (`▁`) and the longest lap (`█`). If
+    /// all laps are equal, they are drawn as `▄`. If there are no laps, the chart is empty.
+    pub fn sparkline(&self) -> String {
+        /// The block characters, from lowest to highest.
+        const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+        let (min, max): (u64, u64) = match (self.laps.iter().min(), self.laps.iter().max()) {
+            (Some(&min), Some(&max)) => (min, max),
+            _ => return String::new()
+        };
+
+        self.laps.iter()
+            .map(|&lap| {
+                if min == max {
+                    return BLOCKS[3];
+                }
+
+                let level: u128 = u128::from(lap - min) * (BLOCKS.len() as u128 - 1) / u128::from(max - min);
+                BLOCKS[level as usize]
+            })
+            .collect()
+    }
+
+    /// Get the name of the stopwatch's state at run time: `Initialized`, `Running`, `Paused`, or `Stopped`.
+    pub fn state(&self) -> &'static str {
+        let type_name: &'static str = any::type_name::<State>();
+        type_name.rsplit("::").next().unwrap_or(type_name)
+    }
+
+    /// Get a compact, single-line summary of the measurements, e.g. for log messages.
+    ///
+    /// The summary contains the number of laps and the total time, and, if there are any laps, the mean, minimum, and
+    /// maximum lap time, e.g. `3 laps, total 600ns, mean 200ns, min 100ns, max 300ns`.
+    ///
+    /// For stopwatches created by [`Stopwatch::peak_tracking()`](#method.peak_tracking), the number of laps is
+    /// [`number_of_laps()`](#method.number_of_laps), and the minimum is omitted since it is not tracked, e.g.
+    /// `3 laps, total 600ns, mean 200ns, max 300ns`.
+    pub fn summary_line(&self) -> String {
+        let number_of_laps: usize = self.number_of_laps();
+        let mut summary: String = format!("{number} {laps}, total {total}",
+                                          number = number_of_laps,
+                                          laps = if number_of_laps == 1 { "lap" } else { "laps" },
+                                          total = time_unit::format_nanoseconds(self.total_time() as f64));
+
+        if self.peak_lap.is_some() {
+            if number_of_laps > 0 {
+                let statistics: LapStatistics = self.statistics();
+                summary += &format!(", mean {mean}, max {max}",
+                                    mean = time_unit::format_nanoseconds(statistics.mean),
+                                    max = time_unit::format_nanoseconds(statistics.max as f64));
+            }
+            return summary;
+        }
+
+        if let (Some(mean), Some(&min), Some(&max)) = (statistics::mean(&self.laps),
+                                                        self.laps.iter().min(),
+                                                        self.laps.iter().max()) {
+            summary += &format!(", mean {mean}, min {min}, max {max}",
+                                mean = time_unit::format_nanoseconds(mean),
+                                min = time_unit::format_nanoseconds(min as f64),
+                                max = time_unit::format_nanoseconds(max as f64));
+        }
+
+        summary
+    }
+
+    /// Get the start and end of each lap relative to the start of the run, e.g. for rendering a timeline.
+    ///
+    /// The `i`-th entry is the sum of all laps before lap `i` and the sum of all laps up to and including lap `i`.
+    pub fn timeline(&self) -> Vec<(u64, u64)> {
+        let mut end: u64 = 0;
+        self.laps.iter()
+            .map(|&lap| {
+                let start: u64 = end;
+                end += lap;
+                (start, end)
+            })
+            .collect()
+    }
+
+    /// Get the total time the stopwatch has been running.
+    ///
+    /// If the stopwatch is still running, the total time is the time from starting the
+    /// stopwatch until now. Otherwise, it is the sum of all laps.
+    ///
+    /// If the total time cannot be represented as a `u64`, it saturates at `u64::MAX`. See
+    /// [`total_time_checked()`](#method.total_time_checked) to detect this case.
+    pub fn total_time(&self) -> u64 {
+        self.total_time.saturating_add(self.get_running_laps_duration())
+    }
+
+    /// Get the total time the stopwatch has been running, or `None` if it cannot be represented as a `u64`.
+    ///
+    /// See [`total_time()`](#method.total_time) for details.
+    pub fn total_time_checked(&self) -> Option<u64> {
+        self.total_time.checked_add(self.get_running_laps_duration())
+    }
+
+    /// Get the total time the stopwatch has been running, or `0` if it cannot be represented as a `u64`.
+    ///
+    /// This is a shortcut for [`total_time_checked()`](#method.total_time_checked)`.unwrap_or(0)`.
+    pub fn total_time_or_zero(&self) -> u64 {
+        self.total_time_checked().unwrap_or(0)
+    }
+
+    /// Get the [total time](#method.total_time) as a duration, rounded to the nearest multiple of the given `unit`.
+    pub fn total_time_rounded_duration(&self, unit: TimeUnit) -> Duration {
+        Duration::from_nanos(unit.round(self.total_time()))
+    }
+
+    /// Get the total time the stopwatch has been running, together with a flag whether it is still changing, i.e.
+    /// whether the stopwatch is running and the total time includes the current lap's duration up to this point.
+    ///
+    /// See [`total_time()`](#method.total_time) for details.
+    pub fn total_time_detail(&self) -> (u64, bool) {
+        (self.total_time(), self.is_running())
+    }
+
+    /// Get the sum of the laps with the indices `i` through `j` (inclusive), e.g. for analyzing a phase of the run, or
+    /// `None` if `i` is greater than `j` or lap `j` does not exist.
+    pub fn total_time_between(&self, i: usize, j: usize) -> Option<u64> {
+        if i > j {
+            return None;
+        }
+
+        self.laps.get(i..=j).map(|laps| laps.iter().sum())
+    }
+
+    /// Get the total time the stopwatch has been running as a floating-point number of nanoseconds, e.g. for computing
+    /// ratios.
+    ///
+    /// Note that an `f64` cannot represent all integers beyond 2^53, so totals of more than about 104 days lose
+    /// precision. See [`total_time()`](#method.total_time) for details.
+    pub fn total_time_f64(&self) -> f64 {
+        self.total_time() as f64
+    }
+
+    /// Get the current lap's duration up to this point if the stopwatch is running, or `0` otherwise.
+    #[inline(always)]
+    fn get_running_laps_duration(&self) -> u64 {
+        match self.start_time {
+            // If the stopwatch is currently running, the total time is the saved total time plus the current lap's
+            // duration up to this point.
+            Some(current_lap_start_time) => {
+                nanoseconds(Instant::now().saturating_duration_since(current_lap_start_time))
+            },
+            None => 0
+        }
+    }
+
+    /// Add the given finished `lap` to the list of laps and the total time, and name it if requested.
+    #[inline(always)]
+    fn save_lap(&mut self, lap: u64) -> Option<usize> {
+        if self.drop_zero_laps && lap == 0 {
+            return None;
+        }
+
+        let is_suspicious: bool = self.is_suspicious_lap(lap);
+        self.total_time += lap;
+        self.next_sequence_id += 1;
+        self.last_saved_lap = Some(lap);
+
+        let total_time: u64 = self.total_time;
+        let _ = self.fire_alarm_if_exceeded(total_time);
+
+        // Generate the automatic name even if the lap is not stored, so the numbering keeps counting all laps.
+        let name: Option<String> = self.auto_name_prefix.as_mut().map(|(prefix, number)| {
+            *number += 1;
+            format!("{prefix}{number}", prefix = prefix, number = *number - 1)
+        });
+
+        let index: usize = self.push_lap(lap)?;
+        if is_suspicious {
+            self.suspicious_laps.push(index);
+        }
+        if let Some(name) = name {
+            self.lap_names.push((index, name));
+        }
+        Some(index)
+    }
+
+    /// Call the alarm's callback and remove the alarm if the given `total_time` exceeds its threshold. Return `true` if
+    /// the callback has been called.
+    #[inline(always)]
+    fn fire_alarm_if_exceeded(&mut self, total_time: u64) -> bool {
+        match self.total_alarm.0 {
+            Some((at_ns, _)) if total_time > at_ns => {},
+            _ => return false
+        }
+
+        if let Some((_, callback)) = self.total_alarm.0.take() {
+            let callback: AlarmCallback = callback.into_inner().unwrap_or_else(PoisonError::into_inner);
+            callback();
+        }
+        true
+    }
+
+    /// Determine if the given new `lap` takes suspiciously long compared to the median of all previous laps.
+    #[inline(always)]
+    fn is_suspicious_lap(&self, lap: u64) -> bool {
+        let multiple: f64 = match self.jitter_guard {
+            Some(multiple) => multiple,
+            None => return false
+        };
+
+        let mut laps: Vec<u64> = self.laps.clone();
+        laps.sort();
+        match statistics::percentile(&laps, 50.0) {
+            Some(median) if median > 0 => lap as f64 > multiple * median as f64,
+            _ => false
+        }
+    }
+
+    /// Add the given finished `lap` to the list of laps, or only track it if just the longest lap is tracked. Return
+    /// the index at which the lap has been stored, or `None` if it has only been tracked.
+    #[inline(always)]
+    fn push_lap(&mut self, lap: u64) -> Option<usize> {
+        if let Some((ref mut max_lap, ref mut number_of_laps)) = self.peak_lap {
+            *max_lap = (*max_lap).max(lap);
+            *number_of_laps += 1;
+            return None;
+        }
+
+        self.laps.push(lap);
+        Some(self.laps.len() - 1)
+    }
+
+    /// Move the stopwatch into the state `NewState`, setting the start time of the current lap to `start_time`.
+    #[inline(always)]
+    fn transition<NewState>(self, start_time: Option<Instant>) -> Stopwatch<NewState> {
+        Stopwatch {
+            laps: self.laps,
+            start_time,
+            paused_lap: self.paused_lap,
+            total_time: self.total_time,
+            notes: self.notes,
+            marks: self.marks,
+            epoch_label: self.epoch_label,
+            lap_names: self.lap_names,
+            auto_name_prefix: self.auto_name_prefix,
+            partial_laps: self.partial_laps,
+            jitter_guard: self.jitter_guard,
+            suspicious_laps: self.suspicious_laps,
+            total_alarm: self.total_alarm,
+            next_sequence_id: self.next_sequence_id,
+            last_saved_lap: self.last_saved_lap,
+            adaptive_sampling: self.adaptive_sampling,
+            drop_zero_laps: self.drop_zero_laps,
+            number_of_pauses: self.number_of_pauses,
+            peak_lap: self.peak_lap,
+            recording_mode: self.recording_mode,
+            cumulative_laps: self.cumulative_laps,
+            #[cfg(feature = "quanta")]
+            precise_start_time: None,
+            state: PhantomData::<NewState>,
+        }
+    }
+}
+
+impl Stopwatch<Initialized> {
+    /// Initialize a new stopwatch without starting it.
+    pub fn new() -> Stopwatch<Initialized> {
+        Stopwatch {
+            laps: Vec::new(),
+            start_time: None,
+            paused_lap: None,
+            total_time: 0,
+            notes: Vec::new(),
+            marks: Vec::new(),
+            epoch_label: None,
+            lap_names: Vec::new(),
+            auto_name_prefix: None,
+            partial_laps: Vec::new(),
+            jitter_guard: None,
+            suspicious_laps: Vec::new(),
+            total_alarm: TotalAlarm::default(),
+            next_sequence_id: 0,
+            last_saved_lap: None,
+            adaptive_sampling: None,
+            drop_zero_laps: false,
+            number_of_pauses: 0,
+            peak_lap: None,
+            recording_mode: RecordingMode::Interval,
+            cumulative_laps: Vec::new(),
+            #[cfg(feature = "quanta")]
+            precise_start_time: None,
+            state: PhantomData::<Initialized>,
+        }
+    }
+
+    /// Initialize a new stopwatch that only tracks its longest lap instead of storing all laps, without starting it.
+    ///
+    /// This is useful for monitors that only care about the worst case: the stopwatch's memory footprint stays
+    /// constant, no matter how many laps are measured. The longest lap is available via
+    /// [`max_lap()`](#method.max_lap), and the number of laps via [`number_of_laps()`](#method.number_of_laps), while
+    /// [`laps()`](#method.laps) is always empty.
+    ///
+    /// All measured laps count as laps, even though they are not stored: they are added to the total time, get a
+    /// sequence ID, and can trigger the total alarm. Summaries such as [`statistics()`](#method.statistics) and
+    /// [`summary_line()`](#method.summary_line) use [`number_of_laps()`](#method.number_of_laps) as the number of laps.
+    /// Per-lap metadata such as names and flags is not kept, since there is no lap to attach it to.
+    pub fn peak_tracking() -> Stopwatch<Initialized> {
+        Stopwatch {
+            peak_lap: Some((0, 0)),
+            ..Stopwatch::new()
+        }
+    }
+
+    /// Start the stopwatch.
+    pub fn start(self) -> Stopwatch<Running> {
+        self.transition(Some(Instant::now()))
+    }
+}
+
+impl Stopwatch<Running> {
+    /// Initialize a new stopwatch and start it.
+    ///
+    /// This an alias for [`Stopwatch::new()`](#method.new)[`.start()`](#method.start)
+    pub fn start_new() -> Stopwatch<Running> {
+        Stopwatch::new().start()
+    }
+
+    /// Time a single call of `f` with a new stopwatch.
+    ///
+    /// Returns the result of `f` together with the stopped stopwatch, which holds the call's duration as its only lap.
+    pub fn measure_once<T, F: FnOnce() -> T>(f: F) -> (T, Stopwatch<Stopped>) {
+        let mut stopwatch: Stopwatch<Running> = Stopwatch::start_new();
+        let result: T = f();
+        let _ = stopwatch.lap();
+        (result, stopwatch.stop())
+    }
+
+    /// Initialize a new stopwatch whose current lap starts `offset` in the future and start it, e.g. to test the
+    /// behavior if the clock goes backwards.
+    #[cfg(test)]
+    pub(crate) fn with_future_start_time(offset: Duration) -> Stopwatch<Running> {
+        let mut stopwatch: Stopwatch<Running> = Stopwatch::start_new();
+        stopwatch.start_time = Some(Instant::now() + offset);
+        stopwatch
+    }
+
+    /// Initialize a new stopwatch with a total time of `total_ns` nanoseconds and start it, e.g. to test the behavior
+    /// near `u64::MAX` without waiting for centuries.
+    #[cfg(test)]
+    pub(crate) fn with_seeded_total(total_ns: u64) -> Stopwatch<Running> {
+        let mut stopwatch: Stopwatch<Running> = Stopwatch::start_new();
+        stopwatch.total_time = total_ns;
+        stopwatch
+    }
+
+    /// Flag all laps saved from now on as suspicious if they take more than `multiple` times the median of all previous
+    /// laps, e.g. because of scheduler preemption on a noisy machine.
+    ///
+    /// The indices of flagged laps are available via [`suspicious_lap_indices()`](#method.suspicious_lap_indices).
+    /// Note that determining the median requires sorting all previous laps each time a lap is saved.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `multiple` is not positive.
+    pub fn with_jitter_guard(mut self, multiple: f64) -> Stopwatch<Running> {
+        assert!(multiple > 0.0, "The multiple of the median must be positive.");
+
+        self.jitter_guard = Some(multiple);
+        self
+    }
+
+    /// Start a new, independent child stopwatch, e.g. to time a sub-task while this stopwatch keeps running.
+    ///
+    /// The child starts now with no laps and a total time of `0`. It only shares the
+    /// [epoch label](#method.epoch_label) with this stopwatch, to correlate their measurements. Afterwards, laps of
+    /// either stopwatch do not affect the other one.
+    pub fn fork(&self) -> Stopwatch<Running> {
+        let mut child: Stopwatch<Running> = Stopwatch::start_new();
+        child.epoch_label = self.epoch_label.clone();
+        child
+    }
+
+    /// Check if the clock used for measuring time is monotonic, i.e. never goes backwards.
+    ///
+    /// The clock is read `samples` times in a tight loop. Returns `true` if each reading is greater than or equal to
+    /// the previous one. This helps to detect quirks of the platform's clock.
+    pub fn verify_monotonic(samples: usize) -> bool {
+        let mut previous: Instant = Instant::now();
+        (0..samples).all(|_| {
+            let current: Instant = Instant::now();
+            let is_monotonic: bool = current >= previous;
+            previous = current;
+            is_monotonic
+        })
+    }
+
+    /// Get the time the stopwatch has been actively running so far: the sum of all finished laps plus the current lap's
+    /// duration up to this point.
+    ///
+    /// Any time the stopwatch has spent paused is not included. This is the same as
+    /// [`total_time()`](#method.total_time), including its saturation at `u64::MAX`.
+    pub fn active_elapsed(&self) -> u64 {
+        self.total_time()
+    }
+
+    /// Start a new lap. Save the last lap's time and return it.
+    ///
+    /// In the [cumulative recording mode](enum.RecordingMode.html#variant.Cumulative), save the current total time
+    /// instead and return it. These totals are kept apart from the laps and from the checkpoints, and can be retrieved
+    /// via [`cumulative_laps()`](#method.cumulative_laps). Since no lap is saved, the
+    /// [sequence number](#method.next_sequence_id) does not change.
+    pub fn lap(&mut self) -> u64 {
+        match self.recording_mode {
+            RecordingMode::Interval => self.lap_at(Instant::now()),
+            RecordingMode::Cumulative => {
+                let total_time: u64 = self.total_time();
+                self.cumulative_laps.push(total_time);
+                total_time
+            },
+        }
+    }
+
+    /// Set how [`lap()`](#method.lap) records the time.
+    ///
+    /// All other methods starting a new lap are not affected.
+    pub fn set_recording_mode(&mut self, mode: RecordingMode) {
+        self.recording_mode = mode;
+    }
+
+    /// Start a new lap. Save the last lap's time and return it, or return `None` if it has been discarded because it
+    /// took no measurable time (see [`set_drop_zero_laps()`](#method.set_drop_zero_laps)).
+    pub fn try_lap(&mut self) -> Option<u64> {
+        self.try_lap_at(Instant::now())
+    }
+
+    /// Set whether laps measured as taking no time at all are discarded instead of saved.
+    ///
+    /// On platforms with a coarse clock resolution, many laps may be measured as `0`. If `drop` is `true`, these laps
+    /// are not saved by any of the lap methods, and [`try_lap()`](#method.try_lap) returns `None` for them. Their time
+    /// is lost, but is below the clock's resolution anyways.
+    pub fn set_drop_zero_laps(&mut self, drop: bool) {
+        self.drop_zero_laps = drop;
+    }
+
+    /// Start a new lap. Save the last lap's time, send it on the given channel, and return it.
+    ///
+    /// If the lap cannot be sent because the receiving end of the channel has been disconnected, the error is ignored:
+    /// the lap is still saved and returned.
+    pub fn lap_and_send(&mut self, sender: &Sender<u64>) -> u64 {
+        let lap: u64 = self.lap_at(Instant::now());
+        let _ = sender.send(lap);
+        lap
+    }
+
+    /// Start a new lap. Save the last lap's time, try to send it on the given bounded channel without blocking, and
+    /// return it.
+    ///
+    /// If the channel is full or its receiving end has been disconnected, the lap is not sent, but it is still saved and
+    /// returned. This keeps the timing thread from blocking on a slow consumer, e.g. for lossy telemetry.
+    pub fn lap_try_send(&mut self, sender: &SyncSender<u64>) -> u64 {
+        let lap: u64 = self.lap_at(Instant::now());
+        let _ = sender.try_send(lap);
+        lap
+    }
+
+    /// Start a new lap. Save the last lap's time and return it together with the absolute time at which the lap
+    /// finished.
+    ///
+    /// The absolute time is the number of nanoseconds since a process-wide epoch on the monotonic clock, taken at the
+    /// same instant the lap was finished. It has no defined relation to wall-clock time, but can be used to correlate
+    /// events within the process.
+    pub fn lap_timestamped(&mut self) -> (u64, u64) {
+        let epoch: Instant = process_epoch();
+        let now: Instant = Instant::now();
+        (self.lap_at(now), nanoseconds(now.saturating_duration_since(epoch)))
+    }
+
+    /// Start a new lap. Save the last lap's time and return it together with its index, e.g. to correlate the laps with
+    /// external arrays.
+    ///
+    /// The index is the lap's [sequence number](#method.next_sequence_id). Until the stopwatch is reset, this is the
+    /// index at which the lap has been stored in the list of laps. Unlike a position in the list, it also exists for
+    /// laps that are only tracked (see [`peak_tracking()`](#method.peak_tracking)). If the lap is dropped (see
+    /// [`set_drop_zero_laps()`](#method.set_drop_zero_laps)), it does not use up its index, so the next saved lap gets
+    /// the same one.
+    pub fn lap_indexed(&mut self) -> (usize, u64) {
+        let index: usize = self.next_sequence_id as usize;
+        (index, self.lap_at(Instant::now()))
+    }
+
+    /// Start a new lap. Save the last lap's time and return it together with its sequence number (see
+    /// [`next_sequence_id()`](#method.next_sequence_id)).
+    pub fn lap_with_id(&mut self) -> (u64, u64) {
+        let id: u64 = self.next_sequence_id;
+        (id, self.lap_at(Instant::now()))
+    }
+
+    /// Start a new lap. Save the last lap's time and return it together with its difference to the previous lap, or
+    /// `None` if there is no previous lap.
+    ///
+    /// A positive difference means the lap was slower than the previous one. The previous lap is the last saved lap,
+    /// even if the stopwatch only tracks its longest lap (see [`Stopwatch::peak_tracking()`](#method.peak_tracking)).
+    /// If the new lap is dropped because it took no measurable time (see
+    /// [`set_drop_zero_laps()`](#method.set_drop_zero_laps)), the difference is `None`, and the previous lap stays the
+    /// same for the next call.
+    pub fn lap_with_delta(&mut self) -> (u64, Option<i64>) {
+        self.lap_with_delta_at(Instant::now())
+    }
+
+    /// Start a new lap. Save the last lap's time under the given `name` and return it.
+    ///
+    /// The name takes precedence over an automatically assigned name (see
+    /// [`set_auto_name_prefix()`](#method.set_auto_name_prefix)). If the lap is not stored in the list of laps, i.e. if
+    /// it has been dropped or only the longest lap is tracked, the name is discarded.
+    pub fn lap_named<N: Into<String>>(&mut self, name: N) -> u64 {
+        let (lap, index): (u64, Option<usize>) = self.lap_stored_at(Instant::now());
+        if let Some(index) = index {
+            self.lap_names.retain(|&(lap_index, _)| lap_index != index);
+            self.lap_names.push((index, name.into()));
+        }
+        lap
+    }
+
+    /// Commit the current lap's progress as a lap and start a new lap. Save the committed lap's time and return it.
+    ///
+    /// This behaves like [`lap()`](#method.lap), but additionally marks the committed lap as partial, i.e. as not
+    /// covering a complete unit of work. The indices of partial laps are available via
+    /// [`partial_lap_indices()`](#method.partial_lap_indices). If the lap is not stored in the list of laps, i.e. if it
+    /// has been dropped or only the longest lap is tracked, it is not marked.
+    pub fn commit_partial_lap(&mut self) -> u64 {
+        let (lap, index): (u64, Option<usize>) = self.lap_stored_at(Instant::now());
+        self.partial_laps.extend(index);
+        lap
+    }
+
+    /// Start a new lap, measuring the last lap with a high-resolution clock if available. Save the last lap's time and
+    /// return it.
+    ///
+    /// With the `quanta` feature enabled, the lap is timed using the CPU's time stamp counter (or the best clock
+    /// available on the platform) if it has been started by a previous call to this method. In all other cases, this
+    /// method behaves exactly like [`lap()`](#method.lap).
+    ///
+    /// Note that the high-resolution clock is calibrated against the system's monotonic clock, so laps timed with both
+    /// clocks are comparable, but not identical to the nanosecond. The time stamp counter may also be unreliable on
+    /// older CPUs or in virtual machines.
+    pub fn lap_precise(&mut self) -> u64 {
+        #[cfg(feature = "quanta")]
+        {
+            let precise_now: quanta::Instant = quanta::Instant::now();
+            let now: Instant = Instant::now();
+            let lap: u64 = match self.precise_start_time {
+                // Only use the high-resolution clock if the current lap has not been started by any other method.
+                Some((start_time, precise_start_time)) if Some(start_time) == self.start_time => {
+                    let lap: u64 = precise_now.duration_since(precise_start_time).as_nanos() as u64;
+                    let _ = self.save_lap(lap);
+                    self.start_time = Some(now);
+                    lap
+                },
+                _ => self.lap_at(now),
+            };
+
+            self.precise_start_time = Some((now, precise_now));
+            lap
+        }
+
+        #[cfg(not(feature = "quanta"))]
+        self.lap_at(Instant::now())
+    }
+
+    /// Get an endless iterator that starts a new lap each time it is advanced, yielding the last lap's time.
+    ///
+    /// This is useful for timing each item of another iterator by zipping them.
+    pub fn interval_iter(&mut self) -> impl Iterator<Item = u64> + '_ {
+        iter::from_fn(move || Some(self.lap_at(Instant::now())))
+    }
+
+    /// Start a new lap only if at least `min_interval_ns` nanoseconds have passed since the last lap. In this case, save
+    /// the last lap's time and return it. Otherwise, the current lap keeps running and `None` is returned.
+    ///
+    /// This is useful for sampling the timing in very hot loops: each saved lap covers all iterations since the previous
+    /// one. It is an alias for [`lap_if_elapsed()`](#method.lap_if_elapsed).
+    pub fn throttled_lap(&mut self, min_interval_ns: u64) -> Option<u64> {
+        self.lap_if_elapsed(min_interval_ns)
+    }
+
+    /// Adaptively sample laps from now on, keeping the number of laps saved by
+    /// [`adaptive_lap()`](#method.adaptive_lap) near `n`, no matter how often it is called.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is `0`.
+    pub fn set_target_lap_count(&mut self, n: usize) {
+        assert!(n > 0, "The target number of laps must not be zero.");
+
+        self.adaptive_sampling = Some(AdaptiveSampling {
+            target_laps: n,
+            stride: 1,
+            calls: 0,
+            first_lap: self.laps.len(),
+        });
+    }
+
+    /// Start a new lap only on every `N`-th call, where `N` adapts to keep the number of laps near the target set by
+    /// [`set_target_lap_count()`](#method.set_target_lap_count). In this case, save the last lap's time and return it.
+    /// Otherwise, the current lap keeps running and `None` is returned.
+    ///
+    /// This is useful for extremely hot loops with an unknown number of iterations. Initially, each call saves a lap.
+    /// Once twice the target number of laps has been saved, each pair of consecutive laps is merged into a single lap and
+    /// `N` is doubled. Thus, there are always between `n` and `2n` sampled laps after at least `n` calls. Laps saved by
+    /// other methods in the meantime are merged as well. The names and flags (e.g. partial or suspicious) of merged
+    /// laps carry over to the merged lap; if both laps are named, the name of the earlier one is kept. If no target has
+    /// been set, this method behaves like [`lap()`](#method.lap).
+    pub fn adaptive_lap(&mut self) -> Option<u64> {
+        let sampling: &mut AdaptiveSampling = match self.adaptive_sampling {
+            Some(ref mut sampling) => sampling,
+            None => return Some(self.lap_at(Instant::now()))
+        };
+
+        sampling.calls += 1;
+        if sampling.calls < sampling.stride {
+            return None;
+        }
+        sampling.calls = 0;
+        let sampling: AdaptiveSampling = *sampling;
+
+        let lap: u64 = self.lap_at(Instant::now());
+        if self.laps.len() - sampling.first_lap >= 2 * sampling.target_laps {
+            let merged_laps: Vec<u64> = self.laps[sampling.first_lap..]
+                .chunks(2)
+                .map(|pair| pair.iter().sum())
+                .collect();
+            self.laps.truncate(sampling.first_lap);
+            self.laps.extend(merged_laps);
+            self.remap_merged_lap_indices(sampling.first_lap);
+            self.adaptive_sampling = Some(AdaptiveSampling {
+                stride: sampling.stride * 2,
+                ..sampling
+            });
+        }
+
+        Some(lap)
+    }
+
+    /// Start a new lap only if the watched value changed, i.e. if `previous` and `current` differ. In this case, save the
+    /// last lap's time and return it. Otherwise, the current lap keeps running and `None` is returned.
+    pub fn lap_on_change<T: PartialEq>(&mut self, previous: &T, current: &T) -> Option<u64> {
+        if previous == current {
+            return None;
+        }
+
+        Some(self.lap_at(Instant::now()))
+    }
+
+    /// Start a new lap only if the current lap has been running for at least `min_ns` nanoseconds. In this case, save
+    /// the last lap's time and return it. Otherwise, the current lap keeps running and `None` is returned.
+    ///
+    /// This is useful in high-frequency loops to coalesce many tiny laps into fewer ones of a minimum duration.
+    pub fn lap_if_elapsed(&mut self, min_ns: u64) -> Option<u64> {
+        self.lap_if_elapsed_at(min_ns, Instant::now())
+    }
+
+    /// Start a new lap, limiting the last lap's time to at most `ceiling_ns` nanoseconds. Save the (possibly clamped) lap
+    /// time and return it together with a flag whether it has been clamped.
+    ///
+    /// This is useful to keep single outliers, e.g. caused by the process being suspended, from dominating the total
+    /// time, while still being able to count how often this happened.
+    pub fn lap_saturating_to(&mut self, ceiling_ns: u64) -> (u64, bool) {
+        self.lap_saturating_to_at(ceiling_ns, Instant::now())
+    }
+
+    /// Start a new lap. Save the last lap's time and return it, asserting that it did not take longer than
+    /// `threshold_ns` nanoseconds.
+    ///
+    /// This is a helper for performance checks in tests.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the last lap took longer than `threshold_ns` nanoseconds. The lap is saved nevertheless. The panic
+    /// message identifies the lap by its [sequence number](#method.next_sequence_id).
+    pub fn lap_and_assert_under(&mut self, threshold_ns: u64) -> u64 {
+        self.lap_and_assert_under_at(threshold_ns, Instant::now())
+    }
+
+    /// Start a new lap unless `f` returns `true` for the last lap's time. In this case, discard the lap and return
+    /// `None`. Otherwise, save the last lap's time and return it.
+    ///
+    /// A discarded lap is not lost: its time stays part of the current lap, so the timing remains continuous. This is
+    /// useful to skip laps that e.g. took an error path.
+    pub fn lap_dropping_if<F: FnOnce(u64) -> bool>(&mut self, f: F) -> Option<u64> {
+        self.lap_dropping_if_at(f, Instant::now())
+    }
+
+    /// Start a new lap only if the [total time](#method.total_time) has crossed a multiple of `boundary_ns` since the
+    /// last lap. In this case, save the last lap's time and return it. Otherwise, the current lap keeps running and
+    /// `None` is returned.
+    ///
+    /// This is useful for sampling aligned to a time grid, e.g. every 100ms. If several multiples have been crossed
+    /// since the last lap, only a single lap is saved.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `boundary_ns` is `0`.
+    pub fn lap_if_crossed_boundary(&mut self, boundary_ns: u64) -> Option<u64> {
+        self.lap_if_crossed_boundary_at(boundary_ns, Instant::now())
+    }
+
+    /// Start a new lap only if the given `flag` is set. In this case, clear the flag, save the last lap's time, and
+    /// return it. Otherwise, the current lap keeps running and `None` is returned.
+    ///
+    /// This is useful for timing until an event occurs on another thread, which signals it by setting the flag.
+    pub fn lap_if_signaled(&mut self, flag: &AtomicBool) -> Option<u64> {
+        if !flag.swap(false, Ordering::SeqCst) {
+            return None;
+        }
+
+        Some(self.lap_at(Instant::now()))
+    }
+
+    /// Finish the current lap, split it into `n` laps of equal duration, and start a new lap. Save the split laps and
+    /// return them.
+    ///
+    /// If the current lap's duration is not divisible by `n`, the remainder is distributed over the first laps, one
+    /// nanosecond each. This is useful if `n` units of repetitive work of roughly equal length have been done since the
+    /// last lap.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is `0`.
+    pub fn lap_batch(&mut self, n: usize) -> Vec<u64> {
+        self.lap_batch_at(n, Instant::now())
+    }
+
+    /// Get the current lap's duration up to this point and restart the current lap, without saving the lap.
+    ///
+    /// Neither the list of laps nor the total time are changed, i.e. the measured time is not accounted for anywhere
+    /// but in the return value. This is useful for free-running interval timers.
+    pub fn read_and_reset(&mut self) -> u64 {
+        self.read_and_reset_at(Instant::now())
+    }
+
+    /// Set a mark with the given `label` at the current total time, without starting a new lap.
+    ///
+    /// If a mark with the same `label` already exists, it is superseded by the new one. This is the same as
+    /// [`checkpoint_named()`](#method.checkpoint_named), ignoring the total time.
+    pub fn mark<L: Into<String>>(&mut self, label: L) {
+        let _ = self.checkpoint_named(label);
+    }
+
+    /// Get the time that has passed since the mark with the given `label` has been set.
+    ///
+    /// Returns `None` if there is no such mark. If the total time has been lowered below the mark since, e.g. via
+    /// [`laps_mut()`](#method.laps_mut) and [`recompute_total()`](#method.recompute_total), the elapsed time is `0`.
+    pub fn elapsed_since_mark(&self, label: &str) -> Option<u64> {
+        self.checkpoint(label)
+            .map(|total_time_at_mark| self.total_time().saturating_sub(total_time_at_mark))
+    }
+
+    /// Save a checkpoint with the given `name` at the current total time, without starting a new lap. Return the total
+    /// time.
+    ///
+    /// If a checkpoint with the same `name` already exists, it is superseded by the new one.
+    pub fn checkpoint_named<N: Into<String>>(&mut self, name: N) -> u64 {
+        let total_time: u64 = self.total_time();
+        self.marks.push((name.into(), total_time));
+        total_time
+    }
+
+    /// Automatically name all laps saved from now on, using the given `prefix` followed by an incrementing number
+    /// starting at `0`, e.g. `step-0`, `step-1`, and so on.
+    ///
+    /// The names can be retrieved via [`lap_name()`](#method.lap_name). Setting a new prefix restarts the numbering.
+    pub fn set_auto_name_prefix<P: Into<String>>(&mut self, prefix: P) {
+        self.auto_name_prefix = Some((prefix.into(), 0));
+    }
+
+    /// Call the given `callback` once the total time exceeds `at_ns` nanoseconds, e.g. to alert on timeouts.
+    ///
+    /// The total time is checked whenever a lap is saved, and on explicit calls to
+    /// [`check_alarm()`](#method.check_alarm). The callback is called at most once. Setting a new alarm replaces the
+    /// previous one. Clones of the stopwatch do not inherit the alarm.
+    ///
+    /// The callback must be `Send`, since the stopwatch stores it: a stopwatch can be moved to or shared with other
+    /// threads, e.g. via [`spawn_reporter()`](#method.spawn_reporter), and the alarm fires on whichever thread saves
+    /// the lap exceeding the threshold.
+    pub fn set_total_alarm<F: FnOnce() + Send + 'static>(&mut self, at_ns: u64, callback: F) {
+        self.total_alarm = TotalAlarm(Some((at_ns, Mutex::new(Box::new(callback)))));
+    }
+
+    /// Call the callback set by [`set_total_alarm()`](#method.set_total_alarm) if the
+    /// [total time](#method.total_time) up to this point exceeds the alarm's threshold. Return `true` if the callback
+    /// has been called.
+    pub fn check_alarm(&mut self) -> bool {
+        let total_time: u64 = self.total_time();
+        self.fire_alarm_if_exceeded(total_time)
+    }
+
+    /// Suspend the stopwatch until the returned guard is dropped.
+    ///
+    /// The time between calling this method and dropping the guard is excluded from the current lap, without having
+    /// to pause and resume the stopwatch:
+    ///
+    /// ```
+    /// # use fine_grained::Stopwatch;
+    /// # fn untimed() {}
+    /// let mut stopwatch = Stopwatch::start_new();
+    /// {
+    ///     let _guard = stopwatch.suspend_guard();
+    ///     untimed();
+    /// }
+    /// let lap: u64 = stopwatch.lap();
+    /// ```
+    pub fn suspend_guard(&mut self) -> SuspendGuard<'_> {
+        SuspendGuard {
+            suspended_at: Instant::now(),
+            stopwatch: self,
+        }
+    }
+
+    /// Get a guard that saves a [checkpoint](#method.checkpoint_named) with the given `name` at the total time at which
+    /// it is dropped, e.g. to record when a scope ends.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use fine_grained::Stopwatch;
+    /// # fn load() {}
+    /// let mut stopwatch = Stopwatch::start_new();
+    /// {
+    ///     let _guard = stopwatch.checkpoint_guard("loaded");
+    ///     load();
+    /// }
+    /// assert!(stopwatch.checkpoint("loaded").is_some());
+    /// ```
+    pub fn checkpoint_guard<N: Into<String>>(&mut self, name: N) -> CheckpointGuard<'_> {
+        CheckpointGuard {
+            stopwatch: self,
+            name: name.into(),
+        }
+    }
+
+    /// Suspend the stopwatch while waiting for `poll` to return `true`.
+    ///
+    /// `poll` is called repeatedly in a busy loop, so this occupies a CPU core for the whole wait. The waiting time is
+    /// excluded from the current lap, like with [`suspend_guard()`](#method.suspend_guard).
+    pub fn pause_until<F: FnMut() -> bool>(&mut self, mut poll: F) {
+        let _guard: SuspendGuard<'_> = self.suspend_guard();
+        while !poll() {}
+    }
+
+    /// Run the given closure `f` without timing it, then start a new lap. Return the closure's result and the last lap's
+    /// time.
+    ///
+    /// The start time of the current lap is moved forward by the execution time of `f`, so the saved lap excludes it.
+    /// This is a lightweight alternative to pausing and resuming the stopwatch around `f`.
+    pub fn lap_excluding<T, F: FnOnce() -> T>(&mut self, f: F) -> (T, u64) {
+        let excluded_start_time: Instant = Instant::now();
+        let result: T = f();
+        let excluded_time: Duration = Instant::now().saturating_duration_since(excluded_start_time);
+
+        self.start_time = self.start_time.map(|start_time| start_time + excluded_time);
+        (result, self.lap_at(Instant::now()))
+    }
+
+    /// Time the given closure `f` as a new lap and return its result.
+    ///
+    /// The lap boundary is reset right before `f` is called, i.e. the time since the last lap is discarded and the
+    /// recorded lap only contains the execution time of `f`.
+    pub fn record<T, F: FnOnce() -> T>(&mut self, f: F) -> T {
+        self.start_time = Some(Instant::now());
+        let result: T = f();
+        let _ = self.lap_at(Instant::now());
+        result
+    }
+
+    /// Time the given closure `f` for each of the given `items` as a new lap, and return the results together with the
+    /// lap times, in the order of the items.
+    ///
+    /// Like with [`record()`](#method.record), the lap boundary is reset right before each call of `f`, i.e. each
+    /// recorded lap only contains the execution time of `f` for the respective item.
+    pub fn batch_record<I: IntoIterator, T, F: FnMut(I::Item) -> T>(&mut self, items: I, mut f: F) -> Vec<(T, u64)> {
+        items.into_iter()
+            .map(|item| {
+                self.start_time = Some(Instant::now());
+                let result: T = f(item);
+                (result, self.lap_at(Instant::now()))
+            })
+            .collect()
+    }
+
+    /// Time the given closure `f` as a new lap with the given `label` as its name, and return its result.
+    ///
+    /// This is a named version of [`record()`](#method.record), useful for timing a program phase by phase.
+    pub fn measure<L: Into<String>, T, F: FnOnce() -> T>(&mut self, label: L, f: F) -> T {
+        self.start_time = Some(Instant::now());
+        let result: T = f();
+        let _ = self.lap_named(label);
+        result
+    }
+
+    /// Time the given fallible closure `f` as a new lap with the given `name`, and return its result.
+    ///
+    /// The named lap is saved regardless of whether `f` succeeds or fails. See [`measure()`](#method.measure) for
+    /// details.
+    pub fn try_measure<N: Into<String>, T, E, F: FnOnce() -> Result<T, E>>(&mut self, name: N, f: F) -> Result<T, E> {
+        self.measure(name, f)
+    }
+
+    /// Finish the current lap and immediately pause the stopwatch.
+    pub fn lap_and_pause(mut self) -> (u64, Stopwatch<Paused>) {
+        let lap: u64 = self.finish_current_lap();
+        self.number_of_pauses += 1;
+
+        // There is no lap in progress, thus a new lap will be started on resume.
+        self.paused_lap = None;
+
+        let stopwatch: Stopwatch<Paused> = self.transition(None);
+
+        (lap, stopwatch)
+    }
+
+    /// Finish the current lap and immediately stop the stopwatch.
+    pub fn lap_and_stop(mut self) -> (u64, Stopwatch<Stopped>) {
+        let lap: u64 = self.finish_current_lap();
+        (lap, self.stop())
+    }
+
+    /// Pause the stopwatch.
+    ///
+    /// The current lap is paused with its duration at this time, and continued on [`resume()`](#method.resume). While
+    /// paused, it is not part of the list of laps.
+    pub fn pause(mut self) -> Stopwatch<Paused> {
+        // Store how long the current lap has been running so far.
+        self.paused_lap = Some(self.get_current_laps_duration());
+        self.number_of_pauses += 1;
+
+        self.transition(None)
+    }
+
+    /// Stop the stopwatch.
+    pub fn stop(self) -> Stopwatch<Stopped> {
+        self.transition(None)
+    }
+
+    /// Move the names and flags of all laps from `first_lap` on to the laps they have been merged into, after each
+    /// pair of consecutive laps from `first_lap` on has been merged into a single lap.
+    fn remap_merged_lap_indices(&mut self, first_lap: usize) {
+        let merged_index = |index: usize| if index < first_lap { index } else { first_lap + (index - first_lap) / 2 };
+
+        // If both merged laps are named, keep the name of the earlier lap.
+        let mut lap_names: Vec<(usize, String)> = self.lap_names.drain(..).collect();
+        lap_names.sort_by_key(|&(index, _)| index);
+        for (index, name) in lap_names {
+            let index: usize = merged_index(index);
+            if self.lap_names.iter().all(|&(named_index, _)| named_index != index) {
+                self.lap_names.push((index, name));
+            }
+        }
+
+        for indices in [&mut self.partial_laps, &mut self.suspicious_laps] {
+            for index in indices.iter_mut() {
+                *index = merged_index(*index);
+            }
+            indices.dedup();
+        }
+    }
+
+    /// Start a new lap at the time `now`. Save the last lap's time and return it.
+    #[inline(always)]
+    fn lap_at(&mut self, now: Instant) -> u64 {
+        self.lap_stored_at(now).0
+    }
+
+    /// Start a new lap at the time `now`. Save the last lap's time and return it together with the index at which it
+    /// has been stored in the list of laps, or `None` if it has not been stored.
+    #[inline(always)]
+    fn lap_stored_at(&mut self, now: Instant) -> (u64, Option<usize>) {
+        let lap: u64 = self.get_current_laps_duration_at(now);
+        let index: Option<usize> = self.save_lap(lap);
+        self.start_time = Some(now);
+        (lap, index)
+    }
+
+    /// Start a new lap at the time `now`. Save the last lap's time and return it unless it has been discarded.
+    #[inline(always)]
+    fn try_lap_at(&mut self, now: Instant) -> Option<u64> {
+        let lap: u64 = self.lap_at(now);
+        if self.drop_zero_laps && lap == 0 {
+            return None;
+        }
+
+        Some(lap)
+    }
+
+    /// Start a new lap at the time `now` if the current lap has been running for at least `min_ns` nanoseconds.
+    #[inline(always)]
+    fn lap_if_elapsed_at(&mut self, min_ns: u64, now: Instant) -> Option<u64> {
+        if self.get_current_laps_duration_at(now) < min_ns {
+            return None;
+        }
+
+        Some(self.lap_at(now))
+    }
+
+    /// Start a new lap at the time `now`. Save the last lap's time and return it with its difference to the previous lap.
+    #[inline(always)]
+    fn lap_with_delta_at(&mut self, now: Instant) -> (u64, Option<i64>) {
+        let previous_lap: Option<u64> = self.last_saved_lap;
+        let id: u64 = self.next_sequence_id;
+        let lap: u64 = self.lap_at(now);
+
+        // The sequence number only advances if the lap has been saved.
+        if self.next_sequence_id == id {
+            return (lap, None);
+        }
+        (lap, previous_lap.map(|previous_lap| lap as i64 - previous_lap as i64))
+    }
+
+    /// Start a new lap at the time `now` if the total time has crossed a multiple of `boundary_ns` since the last lap.
+    #[inline(always)]
+    fn lap_if_crossed_boundary_at(&mut self, boundary_ns: u64, now: Instant) -> Option<u64> {
+        assert!(boundary_ns > 0, "The boundary must not be zero.");
+
+        let total_time: u64 = self.total_time.saturating_add(self.get_current_laps_duration_at(now));
+        if total_time / boundary_ns == self.total_time / boundary_ns {
+            return None;
+        }
+
+        Some(self.lap_at(now))
+    }
+
+    /// Start a new lap at the time `now`, clamping the last lap's time to at most `ceiling_ns` nanoseconds.
+    #[inline(always)]
+    fn lap_saturating_to_at(&mut self, ceiling_ns: u64, now: Instant) -> (u64, bool) {
+        let duration: u64 = self.get_current_laps_duration_at(now);
+        let lap: u64 = duration.min(ceiling_ns);
+        let _ = self.save_lap(lap);
+        self.start_time = Some(now);
+        (lap, lap < duration)
+    }
+
+    /// Start a new lap at the time `now`, asserting that the last lap did not take longer than `threshold_ns`.
+    #[inline(always)]
+    fn lap_and_assert_under_at(&mut self, threshold_ns: u64, now: Instant) -> u64 {
+        let id: u64 = self.next_sequence_id;
+        let lap: u64 = self.lap_at(now);
+        assert!(lap <= threshold_ns, "Lap {id} took {lap}ns, which exceeds the threshold of {threshold}ns.",
+                id = id, lap = lap, threshold = threshold_ns);
+        lap
+    }
+
+    /// Start a new lap at the time `now` unless `f` returns `true` for the last lap's time.
+    #[inline(always)]
+    fn lap_dropping_if_at<F: FnOnce(u64) -> bool>(&mut self, f: F, now: Instant) -> Option<u64> {
+        if f(self.get_current_laps_duration_at(now)) {
+            return None;
+        }
+
+        Some(self.lap_at(now))
+    }
+
+    /// Finish the current lap at the time `now`, split it into `n` equal laps, and start a new lap.
+    #[inline(always)]
+    fn lap_batch_at(&mut self, n: usize, now: Instant) -> Vec<u64> {
+        assert!(n > 0, "The number of laps in a batch must not be zero.");
+
+        let duration: u64 = self.get_current_laps_duration_at(now);
+        let share: u64 = duration / n as u64;
+        let remainder: usize = (duration % n as u64) as usize;
+        let batch: Vec<u64> = (0..n)
+            .map(|i| if i < remainder { share + 1 } else { share })
+            .collect();
+
+        for &lap in &batch {
+            let _ = self.save_lap(lap);
+        }
+        self.start_time = Some(now);
+        batch
+    }
+
+    /// Get the current lap's duration up to the time `now` and restart the current lap, without saving the lap.
+    #[inline(always)]
+    fn read_and_reset_at(&mut self, now: Instant) -> u64 {
+        let duration: u64 = self.get_current_laps_duration_at(now);
+        self.start_time = Some(now);
+        duration
+    }
+
+    /// Finish the current lap: get its duration and add it to the list of laps and the total time.
+    #[inline(always)]
+    fn finish_current_lap(&mut self) -> u64 {
+        self.finish_current_lap_at(Instant::now())
+    }
+
+    /// Finish the current lap at the time `now`: get its duration and add it to the list of laps and the total time.
+    #[inline(always)]
+    fn finish_current_lap_at(&mut self, now: Instant) -> u64 {
+        let lap: u64 = self.get_current_laps_duration_at(now);
+        let _ = self.save_lap(lap);
+        lap
+    }
+
+    /// Get the current lap's duration up to this point..
+    #[inline(always)]
+    pub(crate) fn get_current_laps_duration(&self) -> u64 {
+        self.get_current_laps_duration_at(Instant::now())
+    }
+
+    /// Get the current lap's duration up to the time `now`.
+    ///
+    /// If `now` is before the start of the current lap (i.e. if the clock went backwards), the duration is `0`.
+    #[inline(always)]
+    fn get_current_laps_duration_at(&self, now: Instant) -> u64 {
+        match self.start_time {
+            Some(start_time) => nanoseconds(now.saturating_duration_since(start_time)),
+            None => unreachable!()
+        }
+    }
+}
+
+/// Get the number of nanoseconds in the given `duration`, saturating at `u64::MAX`.
+#[inline(always)]
+fn nanoseconds(duration: Duration) -> u64 {
+    duration.as_nanos().min(u128::from(u64::MAX)) as u64
+}
+
+/// Get the process-wide epoch of absolute timestamps, i.e. the first time this function has been called.
+fn process_epoch() -> Instant {
+    /// The process-wide epoch, initialized on first use.
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    *EPOCH.get_or_init(Instant::now)
+}
+
+/// The state of the adaptive sampling of laps.
+#[derive(Clone, Copy, Debug)]
+struct AdaptiveSampling {
+    /// The target number of sampled laps.
+    target_laps: usize,
+
+    /// The number of calls per sampled lap.
+    stride: u64,
+
+    /// The number of calls since the last sampled lap.
+    calls: u64,
+
+    /// The index of the first sampled lap.
+    first_lap: usize,
+}
+
+/// A one-shot callback to be called once the total time of a stopwatch exceeds a threshold (in nanoseconds).
+///
+/// Since the callback cannot be cloned, a cloned alarm is empty.
+#[derive(Default)]
+struct TotalAlarm(Option<(u64, Mutex<AlarmCallback>)>);
+
+/// The callback of a `TotalAlarm`. It must be `Send` to keep stopwatches `Send` and `Sync`.
+type AlarmCallback = Box<dyn FnOnce() + Send>;
+
+impl Clone for TotalAlarm {
+    /// Create an empty alarm.
+    fn clone(&self) -> TotalAlarm {
+        TotalAlarm(None)
+    }
+}
+
+impl fmt::Debug for TotalAlarm {
+    /// Format the alarm's threshold.
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_tuple("TotalAlarm")
+            .field(&self.0.as_ref().map(|&(at_ns, _)| at_ns))
+            .finish()
+    }
+}
+
+/// A guard excluding the time of its own lifetime from the current lap of a running stopwatch.
+///
+/// See [`Stopwatch::suspend_guard()`](struct.Stopwatch.html#method.suspend_guard) for details.
+#[derive(Debug)]
+pub struct SuspendGuard<'a> {
+    /// The suspended stopwatch.
+    stopwatch: &'a mut Stopwatch<Running>,
+
+    /// The time at which the stopwatch has been suspended.
+    suspended_at: Instant,
+}
+
+impl<'a> Drop for SuspendGuard<'a> {
+    /// Resume the stopwatch by moving the start time of the current lap forward by the suspended time.
+    fn drop(&mut self) {
+        let suspended_time: Duration = Instant::now().saturating_duration_since(self.suspended_at);
+        self.stopwatch.start_time = self.stopwatch.start_time.map(|start_time| start_time + suspended_time);
+    }
+}
+
+/// A guard saving a named checkpoint on a running stopwatch when it is dropped.
+///
+/// See [`Stopwatch::checkpoint_guard()`](struct.Stopwatch.html#method.checkpoint_guard) for details.
+#[derive(Debug)]
+pub struct CheckpointGuard<'a> {
+    /// The stopwatch on which the checkpoint is saved.
+    stopwatch: &'a mut Stopwatch<Running>,
+
+    /// The name of the checkpoint.
+    name: String,
+}
+
+impl<'a> Drop for CheckpointGuard<'a> {
+    /// Save the checkpoint at the current total time.
+    fn drop(&mut self) {
+        let _ = self.stopwatch.checkpoint_named(self.name.as_str());
+    }
+}
+
+impl Stopwatch<Paused> {
+    /// Resume the stopwatch.
+    ///
+    /// If a lap has been paused as well (i.e. [`pause()`](#method.pause) has been called), this lap will be resumed.
+    pub fn resume(mut self) -> Stopwatch<Running> {
+        let paused_lap: u64 = self.paused_lap.take().unwrap_or(0);
+        // The start time of the paused lap dates back to the current time minus the paused lap's duration.
+        let now: Instant = Instant::now();
+        self.transition(Some(now.checked_sub(Duration::from_nanos(paused_lap)).unwrap_or(now)))
+    }
+
+    /// Stop the stopwatch.
+    ///
+    /// If a lap has been paused as well, (i.e. [`pause()`](#method.pause) has been called), this lap will be stopped
+    /// and saved like any other finished lap.
+    pub fn stop(mut self) -> Stopwatch<Stopped> {
+        // There is no paused lap if `lap_and_pause()` has been called.
+        if let Some(paused_lap) = self.paused_lap.take() {
+            let _ = self.save_lap(paused_lap);
+        }
+        self.transition(None)
+    }
+}
+
+impl Stopwatch<Stopped> {
+    /// Combine multiple stopped stopwatches into a single one.
+    ///
+    /// The laps of all given stopwatches are concatenated in the given order and their total times are summed up.
+    pub fn combine(runs: &[Stopwatch<Stopped>]) -> Stopwatch<Stopped> {
+        let mut combined: Stopwatch<Stopped> = Stopwatch::new().transition(None);
+        combined.laps.reserve(runs.iter().map(|run| run.laps.len()).sum());
+
+        for run in runs {
+            let offset: usize = combined.laps.len();
+            combined.lap_names.extend(run.lap_names.iter().map(|(index, name)| (offset + index, name.clone())));
+            combined.partial_laps.extend(run.partial_laps.iter().map(|index| offset + index));
+            combined.suspicious_laps.extend(run.suspicious_laps.iter().map(|index| offset + index));
+            combined.laps.extend_from_slice(&run.laps);
+            combined.total_time += run.total_time;
+            combined.notes.extend_from_slice(&run.notes);
+        }
+
+        combined
+    }
+
+    /// Create a stopped stopwatch from laps encoded by [`delta_encode()`](#method.delta_encode).
+    ///
+    /// The total time is the sum of the decoded laps.
+    pub fn from_delta_encoded(deltas: Vec<i64>) -> Stopwatch<Stopped> {
+        let mut stopwatch: Stopwatch<Stopped> = Stopwatch::new().transition(None);
+        let mut previous: u64 = 0;
+        stopwatch.laps = deltas.into_iter()
+            .map(|delta| {
+                previous = previous.wrapping_add(delta as u64);
+                previous
+            })
+            .collect();
+        stopwatch.recompute_total();
+        stopwatch
+    }
+
+    /// Encode the lap times as differences between successive laps, e.g. for storing many slowly varying laps
+    /// compactly.
+    ///
+    /// The first value is the first lap's absolute time. Laps are restored by
+    /// [`Stopwatch::from_delta_encoded()`](#method.from_delta_encoded). The differences wrap around, so even laps
+    /// longer than `i64::MAX` nanoseconds survive the round trip.
+    pub fn delta_encode(&self) -> Vec<i64> {
+        let mut previous: u64 = 0;
+        self.laps.iter()
+            .map(|&lap| {
+                let delta: i64 = lap.wrapping_sub(previous) as i64;
+                previous = lap;
+                delta
+            })
+            .collect()
+    }
+
+    /// Merge the laps of the `other` run into the laps of this run in linear time, keeping them sorted, and add the
+    /// other run's total time to this run's total time.
+    ///
+    /// This requires the laps of both runs to be sorted in ascending order, e.g. when maintaining a sorted buffer of
+    /// laps for computing percentiles. Otherwise, the merged laps are not sorted. Since the laps change their indices,
+    /// the lap names and the partial and suspicious lap flags of this run are discarded.
+    pub fn merge_preserving_sort(&mut self, other: &Stopwatch<Stopped>) {
+        let mut merged_laps: Vec<u64> = Vec::with_capacity(self.laps.len() + other.laps.len());
+        let mut laps = self.laps.iter().peekable();
+        let mut other_laps = other.laps.iter().peekable();
+        while let (Some(&&lap), Some(&&other_lap)) = (laps.peek(), other_laps.peek()) {
+            if lap <= other_lap {
+                merged_laps.push(lap);
+                let _ = laps.next();
+            } else {
+                merged_laps.push(other_lap);
+                let _ = other_laps.next();
+            }
+        }
+        merged_laps.extend(laps);
+        merged_laps.extend(other_laps);
+
+        self.laps = merged_laps;
+        self.total_time += other.total_time;
+        self.lap_names.clear();
+        self.partial_laps.clear();
+        self.suspicious_laps.clear();
+    }
+
+    /// Get the relative change of this run's `p`-th percentile lap time compared to the one of the `baseline` run, in
+    /// percent.
+    ///
+    /// A positive value means this run is slower than the baseline, e.g. `10.0` if its `p`-th percentile is 10%
+    /// higher. Returns `None` if either run has no laps, or if the baseline's percentile is `0`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is not in the range `[0.0, 100.0]`.
+    pub fn percentile_regression(&self, baseline: &Stopwatch<Stopped>, p: f64) -> Option<f64> {
+        let mut laps: Vec<u64> = self.laps.clone();
+        laps.sort();
+        let mut baseline_laps: Vec<u64> = baseline.laps.clone();
+        baseline_laps.sort();
+
+        let percentile: f64 = statistics::percentile(&laps, p)? as f64;
+        let baseline_percentile: f64 = statistics::percentile(&baseline_laps, p)? as f64;
+        if baseline_percentile == 0.0 {
+            return None;
+        }
+
+        Some((percentile - baseline_percentile) * 100.0 / baseline_percentile)
+    }
+
+    /// Determine if the lap times of this run and the `other` run differ significantly at the significance level
+    /// `alpha`, e.g. `0.05`, or `None` if either run has fewer than eight laps.
+    ///
+    /// The runs are compared with a two-sided Mann–Whitney U test, which makes no assumptions about the distribution of
+    /// the lap times. Returns `Some(true)` if the hypothesis that both runs have the same distribution is rejected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` is not in the range `(0.0, 1.0)`.
+    pub fn differs_significantly(&self, other: &Stopwatch<Stopped>, alpha: f64) -> Option<bool> {
+        assert!(alpha > 0.0 && alpha < 1.0, "The significance level must be in the range (0.0, 1.0).");
+
+        statistics::mann_whitney_p_value(&self.laps, &other.laps).map(|p_value| p_value < alpha)
+    }
+
+    /// Get the period (in laps) of the dominant periodic pattern in the lap times, e.g. a garbage collection every `n`
+    /// iterations, or `None` if there are fewer than four laps or no period stands out.
+    ///
+    /// The period is detected with a fast Fourier transform of the lap sequence: it is the period of the strongest
+    /// frequency component, ignoring the constant part, if its power is at least eight times the average power of all
+    /// components.
+    #[cfg(feature = "rustfft")]
+    pub fn dominant_period(&self) -> Option<usize> {
+        statistics::dominant_period(&self.laps)
+    }
+
+    /// Get a one-line summary of the lap times for humans, e.g. `mean 200.00µs ± 15.00µs over 50 laps`, where the range
+    /// is the standard deviation of the laps.
+    ///
+    /// Returns `no laps` if there are no laps. For stopwatches created by
+    /// [`Stopwatch::peak_tracking()`](#method.peak_tracking), the standard deviation is not tracked and thus omitted,
+    /// e.g. `mean 200.00µs over 50 laps`.
+    pub fn confidence_banner(&self) -> String {
+        if self.peak_lap.is_some() {
+            let number_of_laps: usize = self.number_of_laps();
+            if number_of_laps == 0 {
+                return String::from("no laps");
+            }
+
+            return format!("mean {mean} over {count} {laps}",
+                           mean = time_unit::format_nanoseconds(self.statistics().mean),
+                           count = number_of_laps,
+                           laps = if number_of_laps == 1 { "lap" } else { "laps" });
+        }
+
+        let (mean, standard_deviation): (f64, f64) = match (statistics::mean(&self.laps),
+                                                            statistics::standard_deviation(&self.laps)) {
+            (Some(mean), Some(standard_deviation)) => (mean, standard_deviation),
+            _ => return String::from("no laps"),
+        };
+
+        format!("mean {mean} ± {standard_deviation} over {count} {laps}",
+                mean = time_unit::format_nanoseconds(mean),
+                standard_deviation = time_unit::format_nanoseconds(standard_deviation),
+                count = self.laps.len(),
+                laps = if self.laps.len() == 1 { "lap" } else { "laps" })
+    }
+
+    /// Get the dynamic time warping distance between the lap times of this run and the `other` run, e.g. to compare
+    /// the shape of two runs of different lengths.
+    ///
+    /// The distance is the minimal sum of absolute differences between lap times over all monotonic alignments of the
+    /// two runs, so it is `0.0` for runs of identical shape. If exactly one of the runs has no laps, the distance is
+    /// infinite.
+    pub fn dtw_distance(&self, other: &Stopwatch<Stopped>) -> f64 {
+        if self.laps.is_empty() || other.laps.is_empty() {
+            return if self.laps.len() == other.laps.len() { 0.0 } else { f64::INFINITY };
+        }
+
+        // Only the previous row of the cost matrix is needed to compute the next one.
+        let mut previous_row: Vec<f64> = vec![f64::INFINITY; other.laps.len() + 1];
+        previous_row[0] = 0.0;
+        for &lap in &self.laps {
+            let mut row: Vec<f64> = vec![f64::INFINITY; other.laps.len() + 1];
+            for (j, &other_lap) in other.laps.iter().enumerate() {
+                let cost: f64 = (lap as f64 - other_lap as f64).abs();
+                row[j + 1] = cost + previous_row[j].min(previous_row[j + 1]).min(row[j]);
+            }
+            previous_row = row;
+        }
+
+        previous_row[other.laps.len()]
+    }
+
+    /// Fit an exponential decay towards a steady state to the lap times, e.g. to analyze the warm-up of a benchmark.
+    ///
+    /// Returns the parameters `(a, b, c)` of the least-squares fit `lap = a * exp(-b * index) + c`, where `c` is the
+    /// steady-state lap time, `a` the additional time of the first lap, and `b` the decay rate per lap (searched in the
+    /// range `[0.001, 10]`). Returns `None` if there are fewer than three laps, or if no fit could be determined.
+    pub fn fit_decay(&self) -> Option<(f64, f64, f64)> {
+        statistics::fit_exponential_decay(&self.laps)
+    }
+
+    /// Get the cumulative distribution of the lap times, e.g. for plotting latency CDFs.
+    ///
+    /// For each distinct lap time in ascending order, the result contains the lap time together with the fraction of
+    /// laps less than or equal to it.
+    pub fn cdf(&self) -> Vec<(u64, f64)> {
+        let mut laps: Vec<u64> = self.laps.clone();
+        laps.sort();
+
+        let number_of_laps: f64 = laps.len() as f64;
+        let mut distribution: Vec<(u64, f64)> = Vec::new();
+        for (index, &lap) in laps.iter().enumerate() {
+            // Only add a point for the last occurrence of each lap time.
+            if laps.get(index + 1) != Some(&lap) {
+                distribution.push((lap, (index + 1) as f64 / number_of_laps));
+            }
+        }
+
+        distribution
+    }
+
+    /// Reduce the laps to at most `max_points` values, e.g. for plotting very long runs.
+    ///
+    /// The laps are split into `max_points` consecutive groups of (almost) equal size, and the mean lap time (rounded
+    /// down) of each group is returned. If there are no more than `max_points` laps, all laps are returned unchanged.
+    pub fn downsample(&self, max_points: usize) -> Vec<u64> {
+        let number_of_laps: usize = self.laps.len();
+        if max_points >= number_of_laps {
+            return self.laps.clone();
+        }
+
+        (0..max_points)
+            .map(|group| {
+                let laps: &[u64] = &self.laps[group * number_of_laps / max_points..
+                                              (group + 1) * number_of_laps / max_points];
+                laps.iter().sum::<u64>() / laps.len() as u64
+            })
+            .collect()
+    }
+
+    /// Get a histogram of the lap times as `(bin_low, bin_high, count)` triples, choosing the bin width automatically.
+    ///
+    /// The bin width is determined by the Freedman–Diaconis rule, i.e. twice the interquartile range of the laps
+    /// divided by the cube root of the number of laps, but at least `1`. Each bin covers the laps in the half-open range
+    /// `bin_low..bin_high`; the first bin starts at the shortest lap. If all laps are equal, or if the interquartile
+    /// range is `0`, a single bin containing all laps is returned. If there are no laps, no bins are returned.
+    pub fn histogram_auto(&self) -> Vec<(u64, u64, usize)> {
+        let mut laps: Vec<u64> = self.laps.clone();
+        laps.sort();
+
+        let (min, max): (u64, u64) = match (laps.first(), laps.last()) {
+            (Some(&min), Some(&max)) => (min, max),
+            _ => return Vec::new()
+        };
+
+        let first_quartile: u64 = statistics::percentile(&laps, 25.0).unwrap_or(min);
+        let third_quartile: u64 = statistics::percentile(&laps, 75.0).unwrap_or(max);
+        let interquartile_range: u64 = third_quartile - first_quartile;
+        let width: u64 = if interquartile_range == 0 {
+            max - min + 1
+        } else {
+            let width: f64 = 2.0 * interquartile_range as f64 / (laps.len() as f64).cbrt();
+            (width.round() as u64).max(1)
+        };
+
+        let mut histogram: Vec<(u64, u64, usize)> = (0..(max - min) / width + 1)
+            .map(|bin| (min + bin * width, min + (bin + 1) * width, 0))
+            .collect();
+        for lap in laps {
+            histogram[((lap - min) / width) as usize].2 += 1;
+        }
+
+        histogram
+    }
+
+    /// Write a minimal SVG sparkline of the lap times of the given `width` and `height` (in pixels) to `writer`.
+    ///
+    /// The sparkline is a single polyline with one point per lap. The shortest lap is placed at the bottom and the
+    /// longest lap at the top of the image. If all laps are equal, the line is drawn in the middle.
+    pub fn write_sparkline_svg<W: io::Write>(&self, mut writer: W, width: u32, height: u32) -> io::Result<()> {
+        let min: u64 = self.laps.iter().cloned().min().unwrap_or(0);
+        let max: u64 = self.laps.iter().cloned().max().unwrap_or(0);
+        let x_step: f64 = if self.laps.len() > 1 { f64::from(width) / (self.laps.len() - 1) as f64 } else { 0.0 };
+
+        let points: Vec<String> = self.laps.iter()
+            .enumerate()
+            .map(|(index, &lap)| {
+                let relative_height: f64 = if max > min { (lap - min) as f64 / (max - min) as f64 } else { 0.5 };
+                format!("{x:.1},{y:.1}", x = index as f64 * x_step, y = f64::from(height) * (1.0 - relative_height))
+            })
+            .collect();
+
+        write!(writer,
+               "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+                viewBox=\"0 0 {width} {height}\"><polyline fill=\"none\" stroke=\"black\" points=\"{points}\"/></svg>",
+               width = width,
+               height = height,
+               points = points.join(" "))
+    }
+
+    /// Render the measurements in Prometheus' text exposition format, with all metric names starting with
+    /// `metric_prefix`.
+    ///
+    /// The output contains the total time (`<prefix>_total_nanoseconds`), the number of laps (`<prefix>_lap_count`),
+    /// and, if there are any stored laps, the 50th, 90th, and 99th percentile lap times
+    /// (`<prefix>_lap_nanoseconds{quantile="0.5"}` etc.). For stopwatches created by
+    /// [`Stopwatch::peak_tracking()`](#method.peak_tracking), the number of laps is
+    /// [`number_of_laps()`](#method.number_of_laps), and the percentiles are omitted since the laps are not stored.
+    pub fn to_prometheus_text(&self, metric_prefix: &str) -> String {
+        let mut text: String = format!("{prefix}_total_nanoseconds {total}\n{prefix}_lap_count {count}\n",
+                                       prefix = metric_prefix,
+                                       total = self.total_time,
+                                       count = self.number_of_laps());
+
+        let mut laps: Vec<u64> = self.laps.clone();
+        laps.sort();
+        for &(quantile, p) in &[("0.5", 50.0), ("0.9", 90.0), ("0.99", 99.0)] {
+            if let Some(percentile) = statistics::percentile(&laps, p) {
+                text += &format!("{prefix}_lap_nanoseconds{{quantile=\"{quantile}\"}} {percentile}\n",
+                                 prefix = metric_prefix,
+                                 quantile = quantile,
+                                 percentile = percentile);
+            }
+        }
+
+        text
+    }
+
+    /// Get the lap times as floating-point numbers of nanoseconds, in the order the laps were timed.
+    ///
+    /// This is the shape of samples expected by the estimation functions of statistics crates like `criterion`, which
+    /// take a slice of `f64` values:
+    ///
+    /// ```
+    /// # use fine_grained::Stopwatch;
+    /// # fn estimate_mean(samples: &[f64]) -> f64 { samples.iter().sum::<f64>() / samples.len() as f64 }
+    /// let mut stopwatch = Stopwatch::start_new();
+    /// for _ in 0..10 {
+    ///     stopwatch.lap();
+    /// }
+    ///
+    /// let samples: Vec<f64> = stopwatch.stop().to_criterion_samples();
+    /// let mean: f64 = estimate_mean(&samples);
+    /// ```
+    pub fn to_criterion_samples(&self) -> Vec<f64> {
+        self.laps.iter()
+            .map(|&lap| lap as f64)
+            .collect()
+    }
+
+    /// Render the measurements as a single JSON object, e.g. for diagnostic dumps.
+    ///
+    /// The object contains the total time, the laps in the order they were timed, the notes attached to the stopwatch
+    /// (see [`annotate()`](#method.annotate)), and the [epoch label](#method.set_epoch_label), or `null` if none has
+    /// been set, e.g. `{"total_ns":300,"laps":[100,200],"notes":["ran on battery"],"epoch_label":"run-7"}`.
+    pub fn to_json(&self) -> String {
+        let laps: Vec<String> = self.laps.iter()
+            .map(u64::to_string)
+            .collect();
+        let notes: Vec<String> = self.notes.iter()
+            .map(|note| format!("\"{note}\"", note = escape_json(note)))
+            .collect();
+        let epoch_label: String = self.epoch_label.as_ref()
+            .map_or_else(|| String::from("null"), |label| format!("\"{label}\"", label = escape_json(label)));
+        format!("{{\"total_ns\":{total},\"laps\":[{laps}],\"notes\":[{notes}],\"epoch_label\":{epoch_label}}}",
+                total = self.total_time,
+                laps = laps.join(","),
+                notes = notes.join(","),
+                epoch_label = epoch_label)
+    }
+
+    /// Render the laps in the JSON Lines format, e.g. for log pipelines.
+    ///
+    /// Each lap is a JSON object on its own line with its index and duration, e.g. `{"index":0,"duration_ns":100}`.
+    /// Named laps additionally contain their name, e.g. `{"index":1,"duration_ns":200,"name":"parse"}`. Each line is
+    /// terminated by a newline.
+    pub fn to_json_lines(&self) -> String {
+        self.laps.iter()
+            .enumerate()
+            .map(|(index, lap)| {
+                let name: String = self.lap_name(index)
+                    .map(|name| format!(",\"name\":\"{name}\"", name = escape_json(name)))
+                    .unwrap_or_default();
+                format!("{{\"index\":{index},\"duration_ns\":{lap}{name}}}\n", index = index, lap = lap, name = name)
+            })
+            .collect()
+    }
+
+    /// Keep only the laps for which `f(index, duration)` returns `true`, and recompute the total time.
+    ///
+    /// The order of the remaining laps is preserved. Lap names and partial lap flags move with their laps.
+    pub fn retain_laps<F: FnMut(usize, u64) -> bool>(&mut self, mut f: F) {
+        let mut number_of_retained_laps: usize = 0;
+        let new_indices: Vec<Option<usize>> = self.laps.iter()
+            .enumerate()
+            .map(|(index, &lap)| {
+                if !f(index, lap) {
+                    return None;
+                }
+
+                number_of_retained_laps += 1;
+                Some(number_of_retained_laps - 1)
+            })
+            .collect();
+
+        self.laps = self.laps.iter()
+            .zip(&new_indices)
+            .filter(|(_, new_index)| new_index.is_some())
+            .map(|(&lap, _)| lap)
+            .collect();
+        self.lap_names = self.lap_names.drain(..)
+            .filter_map(|(index, name)| new_indices[index].map(|new_index| (new_index, name)))
+            .collect();
+        self.partial_laps = self.partial_laps.iter()
+            .filter_map(|&index| new_indices[index])
+            .collect();
+        self.suspicious_laps = self.suspicious_laps.iter()
+            .filter_map(|&index| new_indices[index])
+            .collect();
+        self.recompute_total();
+    }
+
+    /// Scale all laps such that the total time equals `reference_total`, e.g. to compare runs from machines of
+    /// different speeds.
+    ///
+    /// Each lap is multiplied by `reference_total / total_time()`; the relative proportions of the laps are preserved.
+    /// The scaled laps are rounded with the largest remainder method, such that they sum to exactly `reference_total`;
+    /// ties are broken in favor of earlier laps. E.g. the laps `[1, 1, 1]` normalized to `10` become `[4, 3, 3]`. The
+    /// total time is set to `reference_total` afterwards. If there are no laps or they took no time at all, the
+    /// stopwatch is left unchanged.
+    pub fn normalize_total_to(&mut self, reference_total: u64) {
+        let sum: u128 = self.laps.iter().map(|&lap| u128::from(lap)).sum();
+        if sum == 0 {
+            return;
+        }
+
+        let mut remainders: Vec<(u128, usize)> = Vec::with_capacity(self.laps.len());
+        for (index, lap) in self.laps.iter_mut().enumerate() {
+            let scaled: u128 = u128::from(*lap) * u128::from(reference_total);
+            // Since each lap is at most the sum of all laps, the scaled lap is at most `reference_total`.
+            *lap = (scaled / sum) as u64;
+            remainders.push((scaled % sum, index));
+        }
+
+        let missing: u64 = reference_total - self.laps.iter().sum::<u64>();
+        remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        for &(_, index) in remainders.iter().take(missing as usize) {
+            self.laps[index] += 1;
+        }
+        self.total_time = reference_total;
+    }
+
+    /// Re-initialize the stopwatch without restarting it.
+    ///
+    /// All measurements are discarded, only the numbering of the laps continues (see
+    /// [`next_sequence_id()`](#method.next_sequence_id)).
+    pub fn reset(self) -> Stopwatch<Initialized> {
+        let mut stopwatch: Stopwatch<Initialized> = Stopwatch::new();
+        stopwatch.next_sequence_id = self.next_sequence_id;
+        stopwatch
+    }
+
+    /// Re-initialize the stopwatch and start it.
+    ///
+    /// This is a shortcut for [`reset()`](#method.reset)[`.start()`](#method.start).
+    pub fn restart(self) -> Stopwatch<Running> {
+        self.reset().start()
+    }
+
+    /// Get the mean lap time after discarding the fastest and slowest `trim_fraction` of all laps.
+    ///
+    /// From each end of the sorted laps, `⌊number_of_laps * trim_fraction⌋` laps are removed before averaging the
+    /// remaining ones. Returns `None` if there are no laps.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `trim_fraction` is not in the range `[0.0, 0.5)`.
+    pub fn trimmed_mean_lap(&self, trim_fraction: f64) -> Option<f64> {
+        assert!((0.0..0.5).contains(&trim_fraction), "The trim fraction must be in the range [0.0, 0.5).");
+
+        if self.laps.is_empty() {
+            return None;
+        }
+
+        let mut laps: Vec<u64> = self.laps.clone();
+        laps.sort();
+
+        let trimmed: usize = (laps.len() as f64 * trim_fraction) as usize;
+        let remaining: &[u64] = &laps[trimmed..laps.len() - trimmed];
+        let sum: f64 = remaining.iter().map(|&lap| lap as f64).sum();
+        Some(sum / remaining.len() as f64)
+    }
+}
+
+impl<'a, State> IntoIterator for &'a Stopwatch<State> {
+    type Item = u64;
+    type IntoIter = Laps<'a>;
+
+    fn into_iter(self) -> Laps<'a> {
+        self.iter()
+    }
+}
+
+impl<State> fmt::Display for Stopwatch<State> {
+    /// Formats the total time using the given formatter.
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{total_time}ns", total_time = self.total_time())
+    }
+}
+
+/// Escape the given `text` for use within a JSON string.
+fn escape_json(text: &str) -> String {
+    let mut escaped: String = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            control if control.is_control() => escaped += &format!("\\u{code:04x}", code = control as u32),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_results)]
+    #![allow(clippy::unwrap_used)]
+
+    use std::slice;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use super::Initialized;
+    use super::RecordingMode;
+    use super::Running;
+    use super::Stopped;
+    use super::Stopwatch;
+    use statistics::LapStatistics;
+    use time_unit::TimeUnit;
+
+    /// Fail to compile if `T` cannot be sent to or shared with other threads.
+    fn assert_send_and_sync<T: Send + Sync>() {}
+
+    /// Get the instant `nanoseconds` after the process epoch, to mock the clock.
+    fn instant(nanoseconds: u64) -> Instant {
+        super::process_epoch() + Duration::from_nanos(nanoseconds)
+    }
+
+    #[test]
+    fn new() {
+        let stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.laps, vec![]);
+        assert_eq!(stopwatch.start_time, None);
+        assert_eq!(stopwatch.total_time, 0);
+    }
+
+    #[test]
+    fn start_new() {
+        let stopwatch = Stopwatch::start_new();
+        assert_eq!(stopwatch.laps, vec![]);
+        assert!(stopwatch.start_time.is_some());
+        assert_eq!(stopwatch.total_time, 0);
+    }
+
+    #[test]
+    fn with_seeded_total() {
+        let stopwatch = Stopwatch::with_seeded_total(1_000);
+        assert!(stopwatch.is_running());
+        assert!(stopwatch.laps.is_empty());
+        assert!(stopwatch.total_time() >= 1_000);
+    }
+
+    #[test]
+    fn total_time_near_max() {
+        let stopwatch = Stopwatch::with_seeded_total(u64::MAX - 1);
+        thread::sleep(Duration::from_millis(1));
+        assert_eq!(stopwatch.total_time(), u64::MAX);
+        assert_eq!(stopwatch.total_time_checked(), None);
+        assert_eq!(stopwatch.total_time_or_zero(), 0);
+
+        let stopwatch = Stopwatch::with_seeded_total(u64::MAX / 2).stop();
+        assert_eq!(stopwatch.total_time(), u64::MAX / 2);
+        assert_eq!(stopwatch.total_time_checked(), Some(u64::MAX / 2));
+    }
+
+    #[test]
+    fn laps_dedup_consecutive() {
+        let mut stopwatch = Stopwatch::start_new().stop();
+        assert_eq!(stopwatch.laps_dedup_consecutive(), vec![]);
+
+        stopwatch.laps = vec![100, 100, 200, 100];
+        assert_eq!(stopwatch.laps_dedup_consecutive(), vec![(100, 2), (200, 1), (100, 1)]);
+    }
+
+    #[test]
+    fn measure_once() {
+        let (result, stopwatch) = Stopwatch::measure_once(|| {
+            thread::sleep(Duration::from_millis(10));
+            42
+        });
+        assert_eq!(result, 42);
+        assert_eq!(stopwatch.laps.len(), 1);
+        assert!(stopwatch.laps[0] >= 10_000_000);
+        assert_eq!(stopwatch.total_time(), stopwatch.laps[0]);
+    }
+
+    #[test]
+    fn clock_going_backwards() {
+        let mut stopwatch = Stopwatch::with_future_start_time(Duration::from_secs(60));
+        assert_eq!(stopwatch.total_time(), 0);
+        assert_eq!(stopwatch.lap(), 0);
+        assert_eq!(stopwatch.laps, vec![0]);
+        assert_eq!(stopwatch.total_time, 0);
+
+        stopwatch.start_time = Some(instant(2_000));
+        assert_eq!(stopwatch.lap_at(instant(1_000)), 0);
+        assert_eq!(stopwatch.lap_at(instant(1_500)), 500);
+    }
+
+    #[test]
+    fn set_recording_mode() {
+        let mut interval = Stopwatch::start_new();
+        assert_eq!(interval.recording_mode(), RecordingMode::Interval);
+        let mut cumulative = Stopwatch::start_new();
+        cumulative.set_recording_mode(RecordingMode::Cumulative);
+        assert_eq!(cumulative.recording_mode(), RecordingMode::Cumulative);
+
+        let mut interval_values: Vec<u64> = Vec::new();
+        let mut cumulative_values: Vec<u64> = Vec::new();
+        for _ in 0..2 {
+            thread::sleep(Duration::from_millis(10));
+            interval_values.push(interval.lap());
+            cumulative_values.push(cumulative.lap());
+        }
+
+        assert_eq!(interval.laps, interval_values);
+        assert!(interval.cumulative_laps().is_empty());
+        assert!(interval_values[1] < cumulative_values[1]);
+
+        assert!(cumulative.laps.is_empty());
+        assert!(cumulative.marks.is_empty());
+        assert_eq!(cumulative.cumulative_laps(), &cumulative_values[..]);
+        assert!(cumulative_values[1] >= cumulative_values[0] + 10_000_000);
+        assert_eq!(cumulative.next_sequence_id(), 0);
+    }
+
+    #[test]
+    fn batch_record() {
+        let mut stopwatch = Stopwatch::start_new();
+        let records: Vec<(u64, u64)> = stopwatch.batch_record(vec![1_u64, 2, 3], |item| {
+            thread::sleep(Duration::from_millis(item * 5));
+            item * 10
+        });
+
+        let results: Vec<u64> = records.iter().map(|&(result, _)| result).collect();
+        let durations: Vec<u64> = records.iter().map(|&(_, duration)| duration).collect();
+        assert_eq!(results, vec![10, 20, 30]);
+        assert_eq!(stopwatch.laps, durations);
+        for (item, &duration) in (1..4).zip(&durations) {
+            assert!(duration >= item * 5_000_000);
+        }
+    }
+
+    #[test]
+    fn fork() {
+        let mut parent = Stopwatch::start_new();
+        parent.set_epoch_label("request-42");
+        thread::sleep(Duration::from_millis(20));
+        parent.lap();
+
+        let mut child = parent.fork();
+        assert!(child.is_running());
+        assert!(child.laps.is_empty());
+        assert!(child.total_time() < parent.total_time());
+        assert_eq!(child.epoch_label(), Some("request-42"));
+
+        child.lap();
+        assert_eq!(parent.laps.len(), 1);
+        parent.lap();
+        assert_eq!(child.laps.len(), 1);
+    }
+
+    #[test]
+    fn verify_monotonic() {
+        assert!(Stopwatch::verify_monotonic(10_000));
+    }
+
+    #[test]
+    fn start() {
+        let stopwatch = Stopwatch::new();
+        let stopwatch = stopwatch.start();
+        assert!(stopwatch.start_time.is_some());
+    }
+
+    #[test]
+    fn active_elapsed() {
+        let mut stopwatch = Stopwatch::start_new();
+        let lap: u64 = stopwatch.lap();
+        assert!(stopwatch.active_elapsed() >= lap);
+
+        let pause = Duration::from_millis(100);
+        let stopwatch = stopwatch.pause();
+        thread::sleep(pause);
+        let stopwatch = stopwatch.resume();
+
+        let active_elapsed: u64 = stopwatch.active_elapsed();
+        assert!(active_elapsed >= lap);
+        assert!(u128::from(active_elapsed) < pause.as_nanos());
+
+        let stopwatch = Stopwatch::with_seeded_total(u64::MAX - 1);
+        thread::sleep(Duration::from_millis(1));
+        assert_eq!(stopwatch.active_elapsed(), u64::MAX);
+    }
+
+    #[test]
+    fn with_jitter_guard() {
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.start_time = Some(instant(0));
+        stopwatch.lap_at(instant(10_000));
+        assert!(stopwatch.jitter_guard.is_none());
+        assert!(stopwatch.suspicious_lap_indices().is_empty());
+
+        let mut stopwatch = stopwatch.with_jitter_guard(5.0);
+        for &now in &[11_000, 12_100, 13_000, 63_000, 64_000, 69_000, 75_000] {
+            stopwatch.lap_at(instant(now));
+        }
+
+        // The laps are 10000, 1000, 1100, 900, 50000, 1000, 5000, 6000 with a running median of 1000 or 1100.
+        assert_eq!(stopwatch.suspicious_lap_indices(), &[4, 7]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn with_jitter_guard_invalid_multiple() {
+        Stopwatch::start_new().with_jitter_guard(0.0);
+    }
+
+    #[test]
+    fn lap() {
+        let mut stopwatch = Stopwatch::start_new();
+        let lap_1: u64 = stopwatch.lap();
+        assert!(lap_1 > 0);
+        assert_eq!(stopwatch.laps.len(), 1);
+        assert_eq!(stopwatch.laps[0], lap_1);
+        assert_eq!(stopwatch.total_time, lap_1);
+
+        let lap_2: u64 = stopwatch.lap();
+        assert!(lap_2 > 0);
+        assert_eq!(stopwatch.laps.len(), 2);
+        assert_eq!(stopwatch.laps[0], lap_1);
+        assert_eq!(stopwatch.laps[1], lap_2);
+        assert_eq!(stopwatch.total_time, lap_1 + lap_2);
+    }
+
+    #[test]
+    fn lap_try_send() {
+        let (sender, receiver) = mpsc::sync_channel(2);
+        let mut stopwatch = Stopwatch::start_new();
+        let laps: Vec<u64> = (0..5).map(|_| stopwatch.lap_try_send(&sender)).collect();
+
+        assert_eq!(stopwatch.laps, laps);
+        assert_eq!(receiver.try_iter().collect::<Vec<u64>>(), &laps[..2]);
+
+        drop(receiver);
+        let lap: u64 = stopwatch.lap_try_send(&sender);
+        assert_eq!(stopwatch.laps.last(), Some(&lap));
+    }
+
+    #[test]
+    fn lap_timestamped() {
+        let mut stopwatch = Stopwatch::start_new();
+        let (lap_1, timestamp_1) = stopwatch.lap_timestamped();
+        thread::sleep(Duration::from_millis(1));
+        let (lap_2, timestamp_2) = stopwatch.lap_timestamped();
+
+        assert!(timestamp_1 < timestamp_2);
+        assert_eq!(timestamp_2 - timestamp_1, lap_2);
+        assert_eq!(stopwatch.laps, vec![lap_1, lap_2]);
+        assert_eq!(stopwatch.start_time, Some(instant(timestamp_2)));
+    }
+
+    #[test]
+    fn lap_indexed() {
+        let mut stopwatch = Stopwatch::start_new();
+        for expected_index in 0..3 {
+            let (index, lap) = stopwatch.lap_indexed();
+            assert_eq!(index, expected_index);
+            assert_eq!(stopwatch.laps[expected_index], lap);
+        }
+    }
+
+    #[test]
+    fn lap_indexed_dropped_zero_lap() {
+        let mut stopwatch = Stopwatch::with_future_start_time(Duration::from_secs(60));
+        stopwatch.set_drop_zero_laps(true);
+        assert_eq!(stopwatch.lap_indexed(), (0, 0));
+        assert!(stopwatch.laps.is_empty());
+
+        thread::sleep(Duration::from_millis(1));
+        let (index, lap) = stopwatch.lap_indexed();
+        assert_eq!(index, 0);
+        assert_eq!(stopwatch.laps, vec![lap]);
+
+        stopwatch.start_time = Some(Instant::now() + Duration::from_secs(60));
+        assert_eq!(stopwatch.lap_indexed(), (1, 0));
+        assert_eq!(stopwatch.laps, vec![lap]);
+    }
+
+    #[test]
+    fn lap_indexed_peak_tracking() {
+        let mut stopwatch = Stopwatch::peak_tracking().start();
+        let (index, lap) = stopwatch.lap_indexed();
+        assert_eq!(index, 0);
+        assert_eq!(stopwatch.max_lap(), Some(lap));
+        assert_eq!(stopwatch.lap_indexed().0, 1);
+        assert!(stopwatch.laps.is_empty());
+    }
+
+    #[test]
+    fn lap_with_id() {
+        let mut stopwatch = Stopwatch::start_new();
+        assert_eq!(stopwatch.next_sequence_id(), 0);
+
+        let (id, lap) = stopwatch.lap_with_id();
+        assert_eq!(id, 0);
+        assert_eq!(stopwatch.laps, vec![lap]);
+        assert_eq!(stopwatch.lap_with_id().0, 1);
+
+        let mut stopwatch = stopwatch.stop().reset().start();
+        assert_eq!(stopwatch.next_sequence_id(), 2);
+        assert_eq!(stopwatch.lap_with_id().0, 2);
+        stopwatch.lap();
+
+        let mut stopwatch = stopwatch.stop().restart();
+        assert_eq!(stopwatch.lap_with_id().0, 4);
+        assert_eq!(stopwatch.laps.len(), 1);
+    }
+
+    #[test]
+    fn lap_with_delta() {
+        let mut stopwatch = Stopwatch::start_new();
+        let (lap, delta) = stopwatch.lap_with_delta();
+        assert_eq!(delta, None);
+        assert_eq!(stopwatch.laps, vec![lap]);
+
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.start_time = Some(instant(1_000));
+        let deltas: Vec<(u64, Option<i64>)> = [1_500, 2_200, 2_500, 2_800]
+            .iter()
+            .map(|&now| stopwatch.lap_with_delta_at(instant(now)))
+            .collect();
+
+        assert_eq!(deltas, vec![(500, None), (700, Some(200)), (300, Some(-400)), (300, Some(0))]);
+    }
+
+    #[test]
+    fn lap_with_delta_dropped_zero_lap() {
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.start_time = Some(instant(1_000));
+        stopwatch.set_drop_zero_laps(true);
+
+        assert_eq!(stopwatch.lap_with_delta_at(instant(1_500)), (500, None));
+        assert_eq!(stopwatch.lap_with_delta_at(instant(1_500)), (0, None));
+        assert_eq!(stopwatch.lap_with_delta_at(instant(2_200)), (700, Some(200)));
+        assert_eq!(stopwatch.laps, vec![500, 700]);
+    }
+
+    #[test]
+    fn lap_with_delta_peak_tracking() {
+        let mut stopwatch = Stopwatch::peak_tracking().start();
+        stopwatch.start_time = Some(instant(1_000));
+
+        assert_eq!(stopwatch.lap_with_delta_at(instant(1_500)), (500, None));
+        assert_eq!(stopwatch.lap_with_delta_at(instant(2_200)), (700, Some(200)));
+        assert_eq!(stopwatch.lap_with_delta_at(instant(2_500)), (300, Some(-400)));
+        assert!(stopwatch.laps.is_empty());
+    }
+
+    #[test]
+    fn lap_named() {
+        let mut stopwatch = Stopwatch::start_new();
+        let lap: u64 = stopwatch.lap_named("warm-up");
+        assert_eq!(stopwatch.laps, vec![lap]);
+        assert_eq!(stopwatch.lap_name(0), Some("warm-up"));
+
+        stopwatch.set_auto_name_prefix("iteration-");
+        stopwatch.lap_named("cool-down");
+        assert_eq!(stopwatch.lap_name(1), Some("cool-down"));
+    }
+
+    #[test]
+    fn lap_named_unstored_lap() {
+        let mut stopwatch = Stopwatch::with_future_start_time(Duration::from_secs(60));
+        stopwatch.set_drop_zero_laps(true);
+        assert_eq!(stopwatch.lap_named("dropped"), 0);
+        assert!(stopwatch.lap_names.is_empty());
+
+        let lap: u64 = stopwatch.lap_named("kept");
+        stopwatch.start_time = Some(Instant::now() + Duration::from_secs(60));
+        stopwatch.lap_named("dropped");
+        assert_eq!(stopwatch.laps, vec![lap]);
+        assert_eq!(stopwatch.lap_name(0), Some("kept"));
+        assert_eq!(stopwatch.lap_names.len(), 1);
+
+        let mut stopwatch = Stopwatch::peak_tracking().start();
+        stopwatch.lap_named("peak");
+        assert!(stopwatch.lap_names.is_empty());
+    }
+
+    #[test]
+    fn commit_partial_lap() {
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.lap();
+        let partial_lap: u64 = stopwatch.commit_partial_lap();
+        stopwatch.lap();
+
+        assert_eq!(stopwatch.laps.len(), 3);
+        assert_eq!(stopwatch.laps[1], partial_lap);
+        assert_eq!(stopwatch.partial_lap_indices(), &[1]);
+        assert_eq!(stopwatch.total_time, stopwatch.laps.iter().sum::<u64>());
+
+        let stopwatch = Stopwatch::combine(&[stopwatch.stop(), Stopwatch::start_new().stop()]);
+        assert_eq!(stopwatch.partial_lap_indices(), &[1]);
+    }
+
+    #[test]
+    fn commit_partial_lap_unstored_lap() {
+        let mut stopwatch = Stopwatch::with_future_start_time(Duration::from_secs(60));
+        stopwatch.set_drop_zero_laps(true);
+        assert_eq!(stopwatch.commit_partial_lap(), 0);
+        assert!(stopwatch.partial_lap_indices().is_empty());
+
+        stopwatch.lap();
+        stopwatch.start_time = Some(Instant::now() + Duration::from_secs(60));
+        stopwatch.commit_partial_lap();
+        assert_eq!(stopwatch.laps.len(), 1);
+        assert!(stopwatch.partial_lap_indices().is_empty());
+
+        let mut stopwatch = Stopwatch::peak_tracking().start();
+        stopwatch.commit_partial_lap();
+        assert!(stopwatch.partial_lap_indices().is_empty());
+    }
+
+    #[test]
+    fn lap_precise() {
+        let sleep_in_ms: u64 = 10;
+        let mut stopwatch = Stopwatch::start_new();
+        for _ in 0..3 {
+            thread::sleep(Duration::from_millis(sleep_in_ms));
+            let lap: u64 = stopwatch.lap_precise();
+            assert!(lap / 1_000_000 >= sleep_in_ms);
+            assert!(lap / 1_000_000 < sleep_in_ms * 100);
+        }
+
+        assert_eq!(stopwatch.laps.len(), 3);
+        assert_eq!(stopwatch.total_time, stopwatch.laps.iter().sum::<u64>());
+    }
+
+    #[cfg(feature = "quanta")]
+    #[test]
+    fn lap_precise_uses_precise_clock() {
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.lap_precise();
+        assert_eq!(stopwatch.precise_start_time.map(|(start_time, _)| start_time), stopwatch.start_time);
+
+        // Any other way of starting a lap falls back to the default clock.
+        stopwatch.lap();
+        assert_ne!(stopwatch.precise_start_time.map(|(start_time, _)| start_time), stopwatch.start_time);
+        assert!(stopwatch.lap_precise() > 0);
+    }
+
+    #[test]
+    fn try_lap() {
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.start_time = Some(instant(1_000));
+        assert_eq!(stopwatch.try_lap_at(instant(1_000)), Some(0));
+        assert_eq!(stopwatch.laps, vec![0]);
+
+        stopwatch.set_drop_zero_laps(true);
+        let recorded: Vec<Option<u64>> = [1_000, 1_200, 1_200, 1_200, 1_500, 1_500]
+            .iter()
+            .map(|&now| stopwatch.try_lap_at(instant(now)))
+            .collect();
+
+        assert_eq!(recorded, vec![None, Some(200), None, None, Some(300), None]);
+        assert_eq!(stopwatch.laps, vec![0, 200, 300]);
+        assert_eq!(stopwatch.total_time, 500);
+
+        stopwatch.lap_at(instant(1_500));
+        assert_eq!(stopwatch.laps, vec![0, 200, 300]);
+
+        thread::sleep(Duration::from_millis(1));
+        assert!(stopwatch.try_lap().is_some());
+    }
+
+    #[test]
+    fn lap_and_send() {
+        let (sender, receiver) = mpsc::channel();
+        let mut stopwatch = Stopwatch::start_new();
+        let lap_1: u64 = stopwatch.lap_and_send(&sender);
+        let lap_2: u64 = stopwatch.lap_and_send(&sender);
+        assert_eq!(receiver.try_iter().collect::<Vec<u64>>(), vec![lap_1, lap_2]);
+
+        drop(receiver);
+        let lap_3: u64 = stopwatch.lap_and_send(&sender);
+        assert_eq!(stopwatch.laps, vec![lap_1, lap_2, lap_3]);
+    }
+
+    #[test]
+    fn interval_iter() {
+        let mut stopwatch = Stopwatch::start_new();
+        let laps: Vec<u64> = (0..3).zip(stopwatch.interval_iter())
+            .map(|(_, lap)| lap)
+            .collect();
+
+        assert_eq!(laps.len(), 3);
+        assert_eq!(stopwatch.laps, laps);
+    }
+
+    #[test]
+    fn throttled_lap() {
+        let mut stopwatch = Stopwatch::start_new();
+        assert_eq!(stopwatch.throttled_lap(u64::MAX), None);
+        assert!(stopwatch.throttled_lap(0).is_some());
+
+        // Simulate a hot loop with one iteration every 70ns.
+        let min_interval_ns: u64 = 300;
+        stopwatch.start_time = Some(instant(0));
+        for now in (1..100).map(|i| i * 70) {
+            let _ = stopwatch.lap_if_elapsed_at(min_interval_ns, instant(now));
+        }
+
+        assert_eq!(stopwatch.laps.len(), 1 + 19);
+        assert!(stopwatch.laps[1..].iter().all(|&lap| lap >= min_interval_ns));
+    }
+
+    #[test]
+    fn adaptive_lap() {
+        let mut stopwatch = Stopwatch::start_new();
+        assert!(stopwatch.adaptive_lap().is_some());
+        assert_eq!(stopwatch.laps.len(), 1);
+
+        let target: usize = 100;
+        stopwatch.set_target_lap_count(target);
+        let sampled: usize = (0..10_000)
+            .filter_map(|_| stopwatch.adaptive_lap())
+            .count();
+
+        let number_of_laps: usize = stopwatch.laps.len() - 1;
+        assert!(sampled > number_of_laps);
+        assert!(number_of_laps >= target && number_of_laps < 2 * target);
+        assert_eq!(stopwatch.total_time, stopwatch.laps.iter().sum::<u64>());
+    }
+
+    #[test]
+    fn adaptive_lap_keeps_names_and_flags() {
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.set_target_lap_count(2);
+        assert!(stopwatch.adaptive_lap().is_some());
+        assert!(stopwatch.adaptive_lap().is_some());
+        stopwatch.lap_named("third");
+        stopwatch.commit_partial_lap();
+        stopwatch.lap_names.push((3, String::from("fourth")));
+        assert_eq!(stopwatch.laps.len(), 4);
+
+        // The fifth lap triggers merging the four laps into two.
+        assert!(stopwatch.adaptive_lap().is_some());
+        assert_eq!(stopwatch.laps.len(), 3);
+        assert_eq!(stopwatch.lap_name(1), Some("third"));
+        assert_eq!(stopwatch.lap_names.len(), 1);
+        assert_eq!(stopwatch.partial_lap_indices(), &[1]);
+        assert_eq!(stopwatch.total_time, stopwatch.laps.iter().sum::<u64>());
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_target_lap_count_zero() {
+        Stopwatch::start_new().set_target_lap_count(0);
+    }
+
+    #[test]
+    fn lap_on_change() {
+        let values: Vec<char> = vec!['a', 'a', 'b', 'b', 'b', 'c', 'a'];
+        let mut stopwatch = Stopwatch::start_new();
+        let recorded: Vec<bool> = values.windows(2)
+            .map(|pair| stopwatch.lap_on_change(&pair[0], &pair[1]).is_some())
+            .collect();
+
+        assert_eq!(recorded, vec![false, true, false, false, true, true]);
+        assert_eq!(stopwatch.laps.len(), 3);
+    }
+
+    #[test]
+    fn lap_if_elapsed() {
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.start_time = Some(instant(1_000));
+
+        assert_eq!(stopwatch.lap_if_elapsed_at(500, instant(1_200)), None);
+        assert_eq!(stopwatch.lap_if_elapsed_at(500, instant(1_499)), None);
+        assert_eq!(stopwatch.laps, vec![]);
+        assert_eq!(stopwatch.start_time, Some(instant(1_000)));
+
+        assert_eq!(stopwatch.lap_if_elapsed_at(500, instant(1_500)), Some(500));
+        assert_eq!(stopwatch.laps, vec![500]);
+        assert_eq!(stopwatch.start_time, Some(instant(1_500)));
+
+        assert_eq!(stopwatch.lap_if_elapsed_at(500, instant(1_800)), None);
+        assert_eq!(stopwatch.lap_if_elapsed_at(500, instant(2_300)), Some(800));
+        assert_eq!(stopwatch.laps, vec![500, 800]);
+        assert_eq!(stopwatch.total_time, 1_300);
+    }
+
+    #[test]
+    fn lap_saturating_to() {
+        let mut stopwatch = Stopwatch::start_new();
+        let (lap, clamped) = stopwatch.lap_saturating_to(u64::MAX);
+        assert!(!clamped);
+        assert_eq!(stopwatch.laps, vec![lap]);
+
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.start_time = Some(instant(1_000));
+        assert_eq!(stopwatch.lap_saturating_to_at(500, instant(1_400)), (400, false));
+        assert_eq!(stopwatch.lap_saturating_to_at(500, instant(2_400)), (500, true));
+        assert_eq!(stopwatch.lap_saturating_to_at(500, instant(2_900)), (500, false));
+        assert_eq!(stopwatch.laps, vec![400, 500, 500]);
+        assert_eq!(stopwatch.total_time, 1_400);
+        assert_eq!(stopwatch.start_time, Some(instant(2_900)));
+    }
+
+    #[test]
+    fn lap_and_assert_under() {
+        let mut stopwatch = Stopwatch::start_new();
+        let lap: u64 = stopwatch.lap_and_assert_under(u64::MAX);
+        assert_eq!(stopwatch.laps, vec![lap]);
+
+        stopwatch.start_time = Some(instant(1_000));
+        assert_eq!(stopwatch.lap_and_assert_under_at(500, instant(1_500)), 500);
+    }
+
+    #[test]
+    #[should_panic(expected = "Lap 1 took 501ns")]
+    fn lap_and_assert_under_slow_lap() {
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.start_time = Some(instant(1_000));
+        stopwatch.lap_and_assert_under_at(500, instant(1_200));
+        stopwatch.lap_and_assert_under_at(500, instant(1_701));
+    }
+
+    #[test]
+    #[should_panic(expected = "Lap 1 took 501ns")]
+    fn lap_and_assert_under_slow_lap_peak_tracking() {
+        let mut stopwatch = Stopwatch::peak_tracking().start();
+        stopwatch.start_time = Some(instant(1_000));
+        stopwatch.lap_and_assert_under_at(500, instant(1_200));
+        stopwatch.lap_and_assert_under_at(500, instant(1_701));
+    }
+
+    #[test]
+    fn lap_dropping_if() {
+        let mut stopwatch = Stopwatch::start_new();
+        assert_eq!(stopwatch.lap_dropping_if(|_| true), None);
+        assert!(stopwatch.lap_dropping_if(|_| false).is_some());
+
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.start_time = Some(instant(1_000));
+        assert_eq!(stopwatch.lap_dropping_if_at(|lap| lap > 500, instant(1_400)), Some(400));
+        assert_eq!(stopwatch.lap_dropping_if_at(|lap| lap > 500, instant(2_000)), None);
+        assert_eq!(stopwatch.laps, vec![400]);
+        assert_eq!(stopwatch.start_time, Some(instant(1_400)));
+
+        // The dropped time is still part of the current lap.
+        assert_eq!(stopwatch.lap_dropping_if_at(|lap| lap > 1_000, instant(2_100)), Some(700));
+        assert_eq!(stopwatch.laps, vec![400, 700]);
+        assert_eq!(stopwatch.total_time, 1_100);
+    }
+
+    #[test]
+    fn lap_if_crossed_boundary() {
+        let mut stopwatch = Stopwatch::start_new();
+        assert_eq!(stopwatch.lap_if_crossed_boundary(u64::MAX), None);
+
+        stopwatch.start_time = Some(instant(1_000));
+        let recorded: Vec<Option<u64>> = [1_030, 1_060, 1_110, 1_150, 1_199, 1_210, 1_450, 1_499]
+            .iter()
+            .map(|&now| stopwatch.lap_if_crossed_boundary_at(100, instant(now)))
+            .collect();
+
+        assert_eq!(recorded, vec![None, None, Some(110), None, None, Some(100), Some(240), None]);
+        assert_eq!(stopwatch.laps, vec![110, 100, 240]);
+        assert_eq!(stopwatch.total_time, 450);
+
+        let mut stopwatch = Stopwatch::with_seeded_total(u64::MAX - 10);
+        stopwatch.start_time = Some(instant(1_000));
+        assert_eq!(stopwatch.lap_if_crossed_boundary_at(1 << 62, instant(1_100)), None);
+    }
+
+    #[test]
+    fn lap_if_signaled() {
+        let flag = Arc::new(AtomicBool::new(false));
+        let mut stopwatch = Stopwatch::start_new();
+        assert_eq!(stopwatch.lap_if_signaled(&flag), None);
+
+        let signal = Arc::clone(&flag);
+        let signaler = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            signal.store(true, Ordering::SeqCst);
+        });
+
+        let lap: u64 = loop {
+            if let Some(lap) = stopwatch.lap_if_signaled(&flag) {
+                break lap;
+            }
+        };
+        signaler.join().unwrap();
+
+        assert!(lap >= 10_000_000);
+        assert!(!flag.load(Ordering::SeqCst));
+        assert_eq!(stopwatch.laps, vec![lap]);
+        assert_eq!(stopwatch.lap_if_signaled(&flag), None);
+    }
+
+    #[test]
+    fn lap_batch() {
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.start_time = Some(instant(1_000));
+
+        let batch: Vec<u64> = stopwatch.lap_batch_at(3, instant(2_000));
+        assert_eq!(batch, vec![334, 333, 333]);
+        assert_eq!(batch.iter().sum::<u64>(), 1_000);
+        assert_eq!(stopwatch.laps, batch);
+        assert_eq!(stopwatch.total_time, 1_000);
+        assert_eq!(stopwatch.start_time, Some(instant(2_000)));
+    }
+
+    #[test]
+    fn read_and_reset() {
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.start_time = Some(instant(1_000));
+
+        assert_eq!(stopwatch.read_and_reset_at(instant(1_500)), 500);
+        assert_eq!(stopwatch.read_and_reset_at(instant(1_800)), 300);
+        assert_eq!(stopwatch.start_time, Some(instant(1_800)));
+        assert_eq!(stopwatch.number_of_laps(), 0);
+        assert_eq!(stopwatch.total_time, 0);
+    }
+
+    #[test]
+    fn elapsed_since_mark() {
+        let sleep_in_ms: u64 = 20;
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.mark("A");
+        thread::sleep(Duration::from_millis(sleep_in_ms));
+        stopwatch.lap();
+
+        assert!(stopwatch.elapsed_since_mark("A").unwrap() / 1_000_000 >= sleep_in_ms);
+        assert_eq!(stopwatch.elapsed_since_mark("B"), None);
+    }
+
+    #[test]
+    fn elapsed_since_mark_lowered_total() {
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.laps_mut().push(60_000_000_000);
+        stopwatch.recompute_total();
+        stopwatch.mark("A");
+
+        stopwatch.laps_mut().clear();
+        stopwatch.recompute_total();
+        assert_eq!(stopwatch.elapsed_since_mark("A"), Some(0));
+    }
+
+    #[test]
+    fn set_auto_name_prefix() {
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.lap();
+        stopwatch.set_auto_name_prefix("step-");
+        stopwatch.lap();
+        stopwatch.lap();
+        stopwatch.lap();
+
+        assert_eq!(stopwatch.lap_name(0), None);
+        assert_eq!(stopwatch.lap_name(1), Some("step-0"));
+        assert_eq!(stopwatch.lap_name(2), Some("step-1"));
+        assert_eq!(stopwatch.lap_name(3), Some("step-2"));
+        assert_eq!(stopwatch.lap_name(4), None);
+    }
+
+    #[test]
+    fn send_and_sync() {
+        assert_send_and_sync::<Stopwatch<Initialized>>();
+        assert_send_and_sync::<Stopwatch<Running>>();
+        assert_send_and_sync::<Stopwatch<Stopped>>();
+
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.set_total_alarm(0, || {});
+        thread::spawn(move || stopwatch.lap()).join().unwrap();
+    }
+
+    #[test]
+    fn set_total_alarm() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.start_time = Some(instant(1_000));
+
+        let alarm_calls = Arc::clone(&calls);
+        stopwatch.set_total_alarm(1_000, move || {
+            alarm_calls.fetch_add(1, Ordering::SeqCst);
+        });
+        assert_eq!(format!("{:?}", stopwatch.total_alarm), "TotalAlarm(Some(1000))");
+        assert_eq!(format!("{:?}", stopwatch.clone().total_alarm), "TotalAlarm(None)");
+
+        stopwatch.lap_at(instant(1_600));
+        stopwatch.lap_at(instant(2_000));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        stopwatch.lap_at(instant(2_100));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        stopwatch.lap_at(instant(3_000));
+        assert!(!stopwatch.check_alarm());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn check_alarm() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let alarm_calls = Arc::clone(&calls);
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.set_total_alarm(u64::MAX, || {});
+        assert!(!stopwatch.check_alarm());
+
+        stopwatch.set_total_alarm(1_000_000, move || {
+            alarm_calls.fetch_add(1, Ordering::SeqCst);
+        });
+        thread::sleep(Duration::from_millis(2));
+        assert!(stopwatch.check_alarm());
+        assert!(!stopwatch.check_alarm());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn checkpoint_guard() {
+        let mut stopwatch = Stopwatch::start_new();
+        {
+            let _guard = stopwatch.checkpoint_guard("scope");
+            thread::sleep(Duration::from_millis(20));
+        }
+        let checkpoint: u64 = stopwatch.checkpoint("scope").unwrap();
+        assert!(checkpoint >= 20_000_000);
+        assert!(checkpoint <= stopwatch.total_time());
+        assert!(stopwatch.laps.is_empty());
+    }
+
+    #[test]
+    fn suspend_guard() {
+        let sleep_in_ms: u64 = 100;
+        let mut stopwatch = Stopwatch::start_new();
+        {
+            let _guard = stopwatch.suspend_guard();
+            thread::sleep(Duration::from_millis(sleep_in_ms));
+        }
+        let lap: u64 = stopwatch.lap();
+
+        assert!(lap / 1_000_000 < sleep_in_ms);
+        assert_eq!(stopwatch.total_time, lap);
+    }
+
+    #[test]
+    fn pause_until() {
+        let wait = Duration::from_millis(50);
+        let mut stopwatch = Stopwatch::start_new();
+        let start_time = Instant::now();
+        stopwatch.pause_until(|| start_time.elapsed() >= wait);
+        let lap: u64 = stopwatch.lap();
+
+        assert!(start_time.elapsed() >= wait);
+        assert!(u128::from(lap) < wait.as_nanos());
+        assert_eq!(stopwatch.total_time, lap);
+    }
+
+    #[test]
+    fn checkpoint_named() {
+        let mut stopwatch = Stopwatch::start_new();
+        let init: u64 = stopwatch.checkpoint_named("init");
+        thread::sleep(Duration::from_millis(1));
+        let loaded: u64 = stopwatch.checkpoint_named("loaded");
+        stopwatch.lap();
+        thread::sleep(Duration::from_millis(1));
+        let ready: u64 = stopwatch.checkpoint_named(String::from("ready"));
+
+        assert_eq!(stopwatch.laps.len(), 1);
+        assert_eq!(stopwatch.checkpoint("init"), Some(init));
+        assert_eq!(stopwatch.checkpoint("loaded"), Some(loaded));
+        assert_eq!(stopwatch.checkpoint("ready"), Some(ready));
+        assert_eq!(stopwatch.checkpoint("done"), None);
+        assert!(init < loaded);
+        assert!(loaded < ready);
+    }
+
+    #[test]
+    fn lap_excluding() {
+        let sleep_in_ms: u64 = 100;
+        let mut stopwatch = Stopwatch::start_new();
+        let (result, lap) = stopwatch.lap_excluding(|| {
+            thread::sleep(Duration::from_millis(sleep_in_ms));
+            42
+        });
+
+        assert_eq!(result, 42);
+        assert!(lap / 1_000_000 < sleep_in_ms);
+        assert_eq!(stopwatch.laps, vec![lap]);
+    }
+
+    #[test]
+    fn record() {
+        let mut stopwatch = Stopwatch::start_new();
+        let sum: u64 = stopwatch.record(|| (1..11).sum());
+        assert_eq!(sum, 55);
+        assert_eq!(stopwatch.laps.len(), 1);
+        assert_eq!(stopwatch.total_time, stopwatch.laps[0]);
+    }
+
+    #[test]
+    fn measure() {
+        let mut stopwatch = Stopwatch::start_new();
+        let sum: u64 = stopwatch.measure("sum", || (1..11).sum());
+        let product: u64 = stopwatch.measure(String::from("product"), || (1..6).product());
+
+        assert_eq!(sum, 55);
+        assert_eq!(product, 120);
+        assert_eq!(stopwatch.laps.len(), 2);
+        assert_eq!(stopwatch.lap_name(0), Some("sum"));
+        assert_eq!(stopwatch.lap_name(1), Some("product"));
+    }
+
+    #[test]
+    fn try_measure() {
+        let mut stopwatch = Stopwatch::start_new();
+        let parsed: Result<u64, _> = stopwatch.try_measure("parse", || "42".parse::<u64>());
+        let failed: Result<u64, _> = stopwatch.try_measure("validate", || "forty-two".parse::<u64>());
+
+        assert_eq!(parsed, Ok(42));
+        assert!(failed.is_err());
+        assert_eq!(stopwatch.laps.len(), 2);
+        assert_eq!(stopwatch.lap_name(0), Some("parse"));
+        assert_eq!(stopwatch.lap_name(1), Some("validate"));
+    }
+
+    #[test]
+    fn stop() {
+        let mut stopwatch = Stopwatch::start_new();
+        let lap: u64 = stopwatch.lap();
+        let stopwatch = stopwatch.stop();
+        assert!(stopwatch.start_time.is_none());
+        assert_eq!(stopwatch.laps.len(), 1);
+        assert_eq!(stopwatch.total_time, lap);
+
+        let mut stopwatch = Stopwatch::start_new();
+        let lap: u64 = stopwatch.lap();
+        let stopwatch = stopwatch.pause();
+        let stopwatch = stopwatch.stop();
+        assert!(stopwatch.start_time.is_none());
+        assert_eq!(stopwatch.laps.len(), 2);
+        assert!(stopwatch.total_time > lap);
+
+        let stopwatch = Stopwatch::start_new();
+        let (lap, stopwatch) = stopwatch.lap_and_pause();
+        let stopwatch = stopwatch.stop();
+        assert!(stopwatch.start_time.is_none());
+        assert_eq!(stopwatch.laps.len(), 1);
+        assert_eq!(stopwatch.total_time, lap);
+    }
+
+    #[test]
+    fn stop_paused_lap() {
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.set_drop_zero_laps(true);
+        let mut stopwatch = stopwatch.pause();
+        stopwatch.paused_lap = Some(0);
+        let stopwatch = stopwatch.stop();
+        assert!(stopwatch.laps.is_empty());
+        assert_eq!(stopwatch.total_time, 0);
+        assert_eq!(stopwatch.next_sequence_id(), 0);
+
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.start_time = Some(instant(1_000));
+        stopwatch.set_auto_name_prefix("step-");
+        stopwatch.lap_at(instant(1_100));
+        let mut stopwatch = stopwatch.pause();
+        stopwatch.paused_lap = Some(500);
+        let stopwatch = stopwatch.stop();
+        assert_eq!(stopwatch.laps, vec![100, 500]);
+        assert_eq!(stopwatch.total_time, 600);
+        assert_eq!(stopwatch.lap_name(1), Some("step-1"));
+        assert_eq!(stopwatch.next_sequence_id(), 2);
+    }
+
+    #[test]
+    fn combine() {
+        let mut first = Stopwatch::start_new().stop();
+        first.laps = vec![100, 200];
+        first.total_time = 300;
+        let mut second = Stopwatch::start_new().stop();
+        second.laps = vec![300];
+        second.total_time = 300;
+        let mut third = Stopwatch::start_new().stop();
+        third.laps = vec![400, 500, 600];
+        third.total_time = 1_500;
+        third.lap_names = vec![(1, String::from("parse"))];
+
+        let runs = vec![first, second, third];
+        let combined = Stopwatch::combine(&runs);
+        assert_eq!(combined.number_of_laps(), runs.iter().map(|run| run.number_of_laps()).sum::<usize>());
+        assert_eq!(combined.laps, vec![100, 200, 300, 400, 500, 600]);
+        assert_eq!(combined.total_time, 2_100);
+        assert_eq!(combined.lap_name(4), Some("parse"));
+        assert_eq!(combined.start_time, None);
+    }
+
+    #[test]
+    fn delta_encode() {
+        let mut stopwatch = Stopwatch::start_new().stop();
+        assert_eq!(stopwatch.delta_encode(), vec![]);
+
+        stopwatch.laps = vec![1_000, 1_010, 990, 990, i64::MAX as u64 + 1_000, 5];
+        let deltas: Vec<i64> = stopwatch.delta_encode();
+        assert_eq!(&deltas[..4], &[1_000, 10, -20, 0]);
+
+        stopwatch.laps.truncate(4);
+        stopwatch.recompute_total();
+        let decoded = Stopwatch::from_delta_encoded(stopwatch.delta_encode());
+        assert_eq!(decoded.laps, stopwatch.laps);
+        assert_eq!(decoded.total_time, 3_990);
+        assert!(!decoded.is_running());
+
+        let decoded = Stopwatch::from_delta_encoded(deltas);
+        assert_eq!(decoded.laps, vec![1_000, 1_010, 990, 990, i64::MAX as u64 + 1_000, 5]);
+    }
+
+    #[test]
+    fn merge_preserving_sort() {
+        let mut first = Stopwatch::start_new().stop();
+        first.laps = vec![100, 300];
+        first.total_time = 400;
+        let mut second = Stopwatch::start_new().stop();
+        second.laps = vec![200, 400];
+        second.total_time = 600;
+
+        first.merge_preserving_sort(&second);
+        assert_eq!(first.laps, vec![100, 200, 300, 400]);
+        assert_eq!(first.total_time, 1_000);
+
+        second.laps = vec![50, 300, 500, 600];
+        second.total_time = 1_450;
+        first.merge_preserving_sort(&second);
+        assert_eq!(first.laps, vec![50, 100, 200, 300, 300, 400, 500, 600]);
+        assert_eq!(first.total_time, 2_450);
+
+        first.merge_preserving_sort(&Stopwatch::start_new().stop());
+        assert_eq!(first.laps.len(), 8);
+    }
+
+    #[test]
+    fn percentile_regression() {
+        let mut baseline = Stopwatch::start_new().stop();
+        let mut stopwatch = Stopwatch::start_new().stop();
+        assert_eq!(stopwatch.percentile_regression(&baseline, 99.0), None);
+
+        baseline.laps = (1..11).map(|i| i * 100).collect();
+        assert_eq!(stopwatch.percentile_regression(&baseline, 99.0), None);
+
+        stopwatch.laps = (1..11).map(|i| i * 110).collect();
+        assert_eq!(stopwatch.percentile_regression(&baseline, 99.0), Some(10.0));
+        assert_eq!(baseline.percentile_regression(&baseline, 99.0), Some(0.0));
+    }
+
+    #[cfg(feature = "rustfft")]
+    #[test]
+    fn dominant_period() {
+        let mut stopwatch = Stopwatch::start_new().stop();
+        assert_eq!(stopwatch.dominant_period(), None);
+
+        stopwatch.laps = (0..40).map(|index| if index % 5 == 0 { 3_000 } else { 1_000 }).collect();
+        assert_eq!(stopwatch.dominant_period(), Some(5));
+    }
+
+    #[test]
+    fn confidence_banner() {
+        let mut stopwatch = Stopwatch::start_new().stop();
+        assert_eq!(stopwatch.confidence_banner(), "no laps");
+
+        stopwatch.laps = vec![185_000, 200_000, 215_000];
+        assert_eq!(stopwatch.confidence_banner(), "mean 200.00µs ± 12.25µs over 3 laps");
+
+        stopwatch.laps = vec![600];
+        assert_eq!(stopwatch.confidence_banner(), "mean 600ns ± 0ns over 1 lap");
+    }
+
+    #[test]
+    fn differs_significantly() {
+        let mut fast = Stopwatch::start_new().stop();
+        fast.laps = (0..20).map(|i| 1_000 + i * 7 % 50).collect();
+        let mut slow = Stopwatch::start_new().stop();
+        slow.laps = (0..20).map(|i| 2_000 + i * 11 % 50).collect();
+
+        assert_eq!(fast.differs_significantly(&slow, 0.05), Some(true));
+        assert_eq!(slow.differs_significantly(&fast, 0.05), Some(true));
+        assert_eq!(fast.differs_significantly(&fast.clone(), 0.05), Some(false));
+
+        slow.laps.truncate(5);
+        assert_eq!(fast.differs_significantly(&slow, 0.05), None);
+    }
+
+    #[test]
+    fn dtw_distance() {
+        let mut run = Stopwatch::start_new().stop();
+        let mut similar = Stopwatch::start_new().stop();
+        let mut dissimilar = Stopwatch::start_new().stop();
+        assert_eq!(run.dtw_distance(&similar), 0.0);
+
+        run.laps = vec![100, 100, 500, 100];
+        assert_eq!(run.dtw_distance(&similar), f64::INFINITY);
+
+        // The same shape, but stretched.
+        similar.laps = vec![100, 500, 500, 100, 100];
+        assert_eq!(run.dtw_distance(&similar), 0.0);
+        assert_eq!(run.dtw_distance(&run.clone()), 0.0);
+
+        similar.laps = vec![110, 490, 100];
+        assert_eq!(run.dtw_distance(&similar), 30.0);
+        assert_eq!(similar.dtw_distance(&run), 30.0);
+
+        dissimilar.laps = vec![900, 50, 50, 900];
+        assert!(run.dtw_distance(&dissimilar) > 1_000.0);
+    }
+
+    #[test]
+    fn fit_decay() {
+        let mut stopwatch = Stopwatch::start_new().stop();
+        assert_eq!(stopwatch.fit_decay(), None);
+
+        // Warm up from 10µs to a steady state of 2µs.
+        stopwatch.laps = (0..50).map(|index| (8_000.0 * (-0.5 * f64::from(index)).exp()) as u64 + 2_000).collect();
+        let (_, _, steady_state) = stopwatch.fit_decay().unwrap();
+        let tail: u64 = stopwatch.laps[stopwatch.laps.len() - 1];
+        assert!((steady_state - tail as f64).abs() < 10.0);
+    }
+
+    #[test]
+    fn cdf() {
+        let mut stopwatch = Stopwatch::start_new().stop();
+        assert_eq!(stopwatch.cdf(), vec![]);
+
+        stopwatch.laps = vec![200, 100, 100];
+        assert_eq!(stopwatch.cdf(), vec![(100, 2.0 / 3.0), (200, 1.0)]);
+    }
+
+    #[test]
+    fn downsample() {
+        let mut stopwatch = Stopwatch::start_new().stop();
+        stopwatch.laps = (0..100).map(|i| i * 10).collect();
+
+        let downsampled: Vec<u64> = stopwatch.downsample(10);
+        assert_eq!(downsampled.len(), 10);
+        assert_eq!(downsampled, (0..10).map(|i| i * 100 + 45).collect::<Vec<u64>>());
+
+        assert_eq!(stopwatch.downsample(3), vec![160, 490, 825]);
+        assert_eq!(stopwatch.downsample(100), stopwatch.laps);
+        assert_eq!(stopwatch.downsample(0), vec![]);
+    }
+
+    #[test]
+    fn histogram_auto() {
+        let mut stopwatch = Stopwatch::start_new().stop();
+        stopwatch.laps = vec![];
+        assert_eq!(stopwatch.histogram_auto(), vec![]);
+
+        stopwatch.laps = vec![300; 5];
+        assert_eq!(stopwatch.histogram_auto(), vec![(300, 301, 5)]);
+
+        // The interquartile range is 48 - 16 = 32, thus the bin width is 2 * 32 / 64^(1/3) = 16.
+        stopwatch.laps = (1..65).rev().collect();
+        assert_eq!(stopwatch.histogram_auto(), vec![(1, 17, 16), (17, 33, 16), (33, 49, 16), (49, 65, 16)]);
+
+        stopwatch.laps.push(100);
+        let histogram: Vec<(u64, u64, usize)> = stopwatch.histogram_auto();
+        assert_eq!(histogram.iter().map(|&(_, _, count)| count).sum::<usize>(), 65);
+        assert_eq!(histogram.last().map(|&(_, _, count)| count), Some(1));
+    }
+
+    #[test]
+    fn write_sparkline_svg() {
+        let mut stopwatch = Stopwatch::start_new().stop();
+        stopwatch.laps = vec![100, 300, 200];
+
+        let mut svg: Vec<u8> = Vec::new();
+        stopwatch.write_sparkline_svg(&mut svg, 100, 20).unwrap();
+        let svg: String = String::from_utf8(svg).unwrap();
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>"));
+        assert!(svg.contains("width=\"100\" height=\"20\""));
+        assert!(svg.contains("points=\"0.0,20.0 50.0,0.0 100.0,10.0\""));
+    }
+
+    #[test]
+    fn to_prometheus_text() {
+        let mut stopwatch = Stopwatch::start_new().stop();
+        assert_eq!(stopwatch.to_prometheus_text("job"), "job_total_nanoseconds 0\njob_lap_count 0\n");
+
+        stopwatch.laps = (1..101).collect();
+        stopwatch.total_time = 5_050;
+        assert_eq!(stopwatch.to_prometheus_text("job"),
+                   "job_total_nanoseconds 5050\n\
+                    job_lap_count 100\n\
+                    job_lap_nanoseconds{quantile=\"0.5\"} 50\n\
+                    job_lap_nanoseconds{quantile=\"0.9\"} 90\n\
+                    job_lap_nanoseconds{quantile=\"0.99\"} 99\n");
+
+        let mut stopwatch = Stopwatch::peak_tracking().start();
+        stopwatch.start_time = Some(instant(1_000));
+        stopwatch.lap_at(instant(1_100));
+        stopwatch.lap_at(instant(1_400));
+        let stopwatch = stopwatch.transition::<Stopped>(None);
+        assert_eq!(stopwatch.to_prometheus_text("job"), "job_total_nanoseconds 400\njob_lap_count 2\n");
+    }
+
+    #[test]
+    fn to_criterion_samples() {
+        let mut stopwatch = Stopwatch::start_new().stop();
+        assert_eq!(stopwatch.to_criterion_samples(), vec![]);
+
+        stopwatch.laps = vec![100, 250, 1_000_000];
+        assert_eq!(stopwatch.to_criterion_samples(), vec![100.0, 250.0, 1_000_000.0]);
+    }
+
+    #[test]
+    fn to_json() {
+        let mut stopwatch = Stopwatch::start_new().stop();
+        stopwatch.laps = vec![];
+        stopwatch.total_time = 0;
+        assert_eq!(stopwatch.to_json(), "{\"total_ns\":0,\"laps\":[],\"notes\":[],\"epoch_label\":null}");
+
+        stopwatch.laps = vec![100, 200];
+        stopwatch.total_time = 300;
+        stopwatch.annotate("ran on battery");
+        stopwatch.annotate("debug \"build\"");
+        assert_eq!(stopwatch.to_json(),
+                   "{\"total_ns\":300,\"laps\":[100,200],\"notes\":[\"ran on battery\",\"debug \\\"build\\\"\"],\
+                    \"epoch_label\":null}");
+
+        stopwatch.set_epoch_label("run-7");
+        assert!(stopwatch.to_json().ends_with(",\"epoch_label\":\"run-7\"}"));
+    }
+
+    #[test]
+    fn to_json_lines() {
+        let mut stopwatch = Stopwatch::start_new().stop();
+        assert_eq!(stopwatch.to_json_lines(), "");
+
+        stopwatch.laps = vec![100, 200, 300];
+        stopwatch.lap_names = vec![(1, String::from("parse \"input\""))];
+        assert_eq!(stopwatch.to_json_lines(),
+                   "{\"index\":0,\"duration_ns\":100}\n\
+                    {\"index\":1,\"duration_ns\":200,\"name\":\"parse \\\"input\\\"\"}\n\
+                    {\"index\":2,\"duration_ns\":300}\n");
+    }
+
+    #[test]
+    fn escape_json() {
+        assert_eq!(super::escape_json("plain"), "plain");
+        assert_eq!(super::escape_json("a \"b\" \\ c\n"), "a \\\"b\\\" \\\\ c\\n");
+        assert_eq!(super::escape_json("\u{1}"), "\\u0001");
+    }
+
+    #[test]
+    fn normalize_total_to() {
+        let mut stopwatch = Stopwatch::start_new().stop();
+        stopwatch.laps = vec![100, 300];
+        stopwatch.total_time = 400;
+
+        stopwatch.normalize_total_to(800);
+        assert_eq!(stopwatch.laps, vec![200, 600]);
+        assert_eq!(stopwatch.total_time(), 800);
+
+        stopwatch.normalize_total_to(3);
+        assert_eq!(stopwatch.laps, vec![1, 2]);
+        assert_eq!(stopwatch.total_time(), 3);
+
+        stopwatch.laps = vec![1, 1, 1];
+        stopwatch.total_time = 3;
+        stopwatch.normalize_total_to(10);
+        assert_eq!(stopwatch.laps, vec![4, 3, 3]);
+        assert_eq!(stopwatch.laps.iter().sum::<u64>(), stopwatch.total_time());
+        assert_eq!(stopwatch.total_time(), 10);
+
+        stopwatch.laps = vec![u64::MAX / 2, u64::MAX / 2];
+        stopwatch.total_time = u64::MAX - 1;
+        stopwatch.normalize_total_to(u64::MAX);
+        assert_eq!(stopwatch.laps, vec![u64::MAX / 2 + 1, u64::MAX / 2]);
+        assert_eq!(stopwatch.total_time(), u64::MAX);
+
+        let mut empty = Stopwatch::start_new().stop();
+        empty.normalize_total_to(800);
+        assert!(empty.laps.is_empty());
+        assert_eq!(empty.total_time(), 0);
+    }
+
+    #[test]
+    fn retain_laps() {
+        let mut stopwatch = Stopwatch::start_new().stop();
+        stopwatch.laps = vec![100, 200, 300];
+        stopwatch.total_time = 600;
+        stopwatch.lap_names = vec![(0, String::from("first")), (2, String::from("third"))];
+
+        stopwatch.retain_laps(|_, lap| lap > 150);
+        assert_eq!(stopwatch.laps, vec![200, 300]);
+        assert_eq!(stopwatch.total_time, 500);
+        assert_eq!(stopwatch.lap_name(0), None);
+        assert_eq!(stopwatch.lap_name(1), Some("third"));
+
+        stopwatch.retain_laps(|index, _| index != 0);
+        assert_eq!(stopwatch.laps, vec![300]);
+        assert_eq!(stopwatch.total_time, 300);
+    }
+
+    #[test]
+    fn reset() {
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.lap();
+        let stopwatch = stopwatch.stop();
+        let stopwatch = stopwatch.reset();
+        assert_eq!(stopwatch.laps, vec![]);
+        assert_eq!(stopwatch.start_time, None);
+        assert_eq!(stopwatch.total_time, 0);
+    }
+
+    #[test]
+    fn restart() {
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.lap();
+        let stopwatch = stopwatch.stop();
+        let stopwatch = stopwatch.restart();
+        assert_eq!(stopwatch.laps, vec![]);
+        assert!(stopwatch.start_time.is_some());
+        assert_eq!(stopwatch.total_time, 0);
+    }
+
+    #[test]
+    fn trimmed_mean_lap() {
+        let mut stopwatch = Stopwatch::start_new().stop();
+        assert_eq!(stopwatch.trimmed_mean_lap(0.2), None);
+
+        stopwatch.laps = vec![90, 10, 1_000, 30, 50, 20, 70, 60, 80, 40];
+        assert_eq!(stopwatch.trimmed_mean_lap(0.2), Some(55.0));
+        assert_eq!(stopwatch.trimmed_mean_lap(0.0), Some(145.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn trimmed_mean_lap_invalid_fraction() {
+        let stopwatch = Stopwatch::start_new().stop();
+        stopwatch.trimmed_mean_lap(0.5);
+    }
+
+    #[test]
+    fn rolling_mean() {
+        let mut stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.rolling_mean(2), vec![]);
+
+        stopwatch.laps = vec![10, 20, 30, 40];
+        assert_eq!(stopwatch.rolling_mean(2), vec![10.0, 15.0, 25.0, 35.0]);
+        assert_eq!(stopwatch.rolling_mean(1), vec![10.0, 20.0, 30.0, 40.0]);
     }
 
     #[test]
-    fn reset() {
-        let mut stopwatch = Stopwatch::start_new();
-        stopwatch.lap();
-        let stopwatch = stopwatch.stop();
-        let stopwatch = stopwatch.reset();
-        assert_eq!(stopwatch.laps, vec![]);
-        assert_eq!(stopwatch.start_time, None);
-        assert_eq!(stopwatch.total_time, 0);
+    fn statistics() {
+        let mut stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.statistics().count, 0);
+
+        stopwatch.laps = (1..101).map(|lap| lap * 10).collect();
+        let statistics = stopwatch.statistics();
+        assert_eq!(statistics.count, 100);
+        assert_eq!(statistics.total, 50_500);
+        assert_eq!(statistics.min, 10);
+        assert_eq!(statistics.max, 1_000);
+        assert_eq!(statistics.mean, 505.0);
+        assert_eq!(statistics.median, 500);
+        assert!((statistics.std_dev - 288.66).abs() < 0.01);
+        assert_eq!(statistics.p90, 900);
+        assert_eq!(statistics.p99, 990);
     }
 
     #[test]
-    fn restart() {
-        let mut stopwatch = Stopwatch::start_new();
-        stopwatch.lap();
-        let stopwatch = stopwatch.stop();
-        let stopwatch = stopwatch.restart();
-        assert_eq!(stopwatch.laps, vec![]);
-        assert!(stopwatch.start_time.is_some());
-        assert_eq!(stopwatch.total_time, 0);
+    fn summary_line() {
+        let mut stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.summary_line(), "0 laps, total 0ns");
+
+        stopwatch.laps = vec![100, 200, 300];
+        stopwatch.total_time = 600;
+        assert_eq!(stopwatch.summary_line(), "3 laps, total 600ns, mean 200ns, min 100ns, max 300ns");
+
+        stopwatch.laps = vec![1_500];
+        stopwatch.total_time = 1_500;
+        assert_eq!(stopwatch.summary_line(), "1 lap, total 1.50µs, mean 1.50µs, min 1.50µs, max 1.50µs");
+    }
+
+    #[test]
+    fn timeline() {
+        let mut stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.timeline(), vec![]);
+
+        stopwatch.laps = vec![100, 200];
+        assert_eq!(stopwatch.timeline(), vec![(0, 100), (100, 300)]);
     }
 
     #[test]
     fn total_time() {
         let mut stopwatch = Stopwatch::start_new();
-        let start_time: u64 = stopwatch.start_time.unwrap();
+        let start_time: Instant = stopwatch.start_time.unwrap();
         let mut total_time: u64 = stopwatch.total_time();
         assert!(total_time > 0);
         assert_eq!(stopwatch.total_time, 0);
@@ -361,6 +3753,51 @@ mod tests {
         assert_eq!(total_time, stopwatch.total_time);
     }
 
+    #[test]
+    fn total_time_or_zero() {
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.lap();
+        let stopwatch = stopwatch.stop();
+        assert_eq!(stopwatch.total_time_checked(), Some(stopwatch.total_time));
+        assert_eq!(stopwatch.total_time_or_zero(), stopwatch.total_time);
+    }
+
+    #[test]
+    fn total_time_detail() {
+        let stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.total_time_detail(), (0, false));
+
+        let mut stopwatch = stopwatch.start();
+        let (_, is_live) = stopwatch.total_time_detail();
+        assert!(is_live);
+
+        let lap: u64 = stopwatch.lap();
+        let stopwatch = stopwatch.stop();
+        assert_eq!(stopwatch.total_time_detail(), (lap, false));
+    }
+
+    #[test]
+    fn total_time_between() {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.laps = vec![100, 200, 300, 400];
+
+        assert_eq!(stopwatch.total_time_between(1, 2), Some(500));
+        assert_eq!(stopwatch.total_time_between(0, 3), Some(1_000));
+        assert_eq!(stopwatch.total_time_between(3, 3), Some(400));
+        assert_eq!(stopwatch.total_time_between(2, 1), None);
+        assert_eq!(stopwatch.total_time_between(2, 4), None);
+    }
+
+    #[test]
+    fn total_time_f64() {
+        let mut stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.total_time_f64(), 0.0);
+
+        stopwatch.total_time = 1_234_567_890;
+        assert_eq!(stopwatch.total_time_f64(), 1_234_567_890.0);
+        assert_eq!(stopwatch.total_time_f64() as u64, stopwatch.total_time());
+    }
+
     #[test]
     fn laps() {
         let mut stopwatch = Stopwatch::start_new();
@@ -371,6 +3808,222 @@ mod tests {
         assert_eq!(stopwatch.laps(), &stopwatch.laps);
     }
 
+    #[test]
+    fn iter() {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.laps = vec![100, 200, 300];
+
+        let laps = stopwatch.iter();
+        assert_eq!(laps.len(), stopwatch.number_of_laps());
+        assert_eq!(laps.rev().collect::<Vec<u64>>(), vec![300, 200, 100]);
+
+        let laps: Vec<u64> = (&stopwatch).into_iter().collect();
+        assert_eq!(laps, vec![100, 200, 300]);
+    }
+
+    #[test]
+    fn recent_lap_rate() {
+        let mut stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.recent_lap_rate(3), 0.0);
+
+        stopwatch.laps = vec![1_000_000_000, 1_000_000_000, 250_000_000, 250_000_000];
+        assert_eq!(stopwatch.recent_lap_rate(0), 0.0);
+        assert_eq!(stopwatch.recent_lap_rate(2), 4.0);
+        assert_eq!(stopwatch.recent_lap_rate(10), 1.6);
+        assert!(stopwatch.recent_lap_rate(2) > stopwatch.recent_lap_rate(stopwatch.laps.len()));
+    }
+
+    #[test]
+    fn laps_mut() {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.laps = vec![100, 200, 300];
+        stopwatch.total_time = 600;
+
+        for lap in stopwatch.laps_mut() {
+            *lap -= 50;
+        }
+        assert_eq!(stopwatch.laps, vec![50, 150, 250]);
+        assert_eq!(stopwatch.total_time, 600);
+
+        stopwatch.recompute_total();
+        assert_eq!(stopwatch.total_time, 450);
+    }
+
+    #[test]
+    fn as_nanos_ptr() {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.laps = vec![100, 200, 300];
+
+        let (pointer, length) = stopwatch.as_nanos_ptr();
+        let laps: &[u64] = unsafe { slice::from_raw_parts(pointer, length) };
+        assert_eq!(laps, &stopwatch.laps()[..]);
+    }
+
+    #[test]
+    fn laps_as_durations_rounded() {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.laps = vec![1_400_000, 1_600_000];
+        assert_eq!(stopwatch.laps_as_durations_rounded(TimeUnit::Milliseconds),
+                   vec![Duration::from_millis(1), Duration::from_millis(2)]);
+    }
+
+    #[test]
+    fn total_time_rounded_duration() {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.total_time = 1_499_000_000;
+        assert_eq!(stopwatch.total_time_rounded_duration(TimeUnit::Seconds), Duration::from_secs(1));
+        assert_eq!(stopwatch.total_time_rounded_duration(TimeUnit::Milliseconds), Duration::from_millis(1_499));
+    }
+
+    #[test]
+    fn lap_percentages_rounded() {
+        let mut stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.lap_percentages_rounded(), vec![]);
+
+        stopwatch.laps = vec![0, 0];
+        assert_eq!(stopwatch.lap_percentages_rounded(), vec![0, 0]);
+
+        stopwatch.laps = vec![100, 100, 100];
+        assert_eq!(stopwatch.lap_percentages_rounded(), vec![34, 33, 33]);
+
+        stopwatch.laps = vec![1, 2, 997];
+        assert_eq!(stopwatch.lap_percentages_rounded(), vec![0, 0, 100]);
+
+        stopwatch.laps = vec![125, 125, 750];
+        assert_eq!(stopwatch.lap_percentages_rounded(), vec![13, 12, 75]);
+    }
+
+    #[test]
+    fn lap_windows() {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.laps = vec![1, 2, 3, 4];
+
+        let expected: Vec<&[u64]> = vec![&[1, 2], &[2, 3], &[3, 4]];
+        assert_eq!(stopwatch.lap_windows(2), expected);
+        assert_eq!(stopwatch.lap_windows(4).len(), 1);
+        assert!(stopwatch.lap_windows(5).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn lap_windows_zero_size() {
+        Stopwatch::new().lap_windows(0);
+    }
+
+    #[test]
+    fn memory_footprint_bytes() {
+        let mut stopwatch = Stopwatch::new();
+        let footprint: usize = stopwatch.memory_footprint_bytes();
+        assert!(footprint >= size_of::<Stopwatch<Initialized>>());
+
+        stopwatch.laps.reserve(100);
+        assert!(stopwatch.memory_footprint_bytes() >= footprint + 100 * size_of::<u64>());
+
+        let footprint: usize = stopwatch.memory_footprint_bytes();
+        stopwatch.annotate("ran on battery");
+        assert!(stopwatch.memory_footprint_bytes() > footprint);
+    }
+
+    #[test]
+    fn lap_trend_slope() {
+        let mut stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.lap_trend_slope(), None);
+
+        stopwatch.laps = vec![1_000];
+        assert_eq!(stopwatch.lap_trend_slope(), None);
+
+        stopwatch.laps = (0..10).map(|index| 1_000 + index * 250).collect();
+        assert_eq!(stopwatch.lap_trend_slope(), Some(250.0));
+
+        stopwatch.laps.reverse();
+        assert_eq!(stopwatch.lap_trend_slope(), Some(-250.0));
+
+        stopwatch.laps = vec![500; 4];
+        assert_eq!(stopwatch.lap_trend_slope(), Some(0.0));
+    }
+
+    #[test]
+    fn max_lap() {
+        let mut stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.max_lap(), None);
+
+        stopwatch.laps = vec![200, 500, 100];
+        assert_eq!(stopwatch.max_lap(), Some(500));
+    }
+
+    #[test]
+    fn peak_tracking() {
+        let stopwatch = Stopwatch::peak_tracking();
+        assert_eq!(stopwatch.max_lap(), None);
+        assert_eq!(stopwatch.number_of_laps(), 0);
+
+        let mut stopwatch = stopwatch.start();
+        stopwatch.start_time = Some(instant(0));
+        let mut now: u64 = 0;
+        for i in 0..10_000 {
+            now += 100 + i * 7 % 1_000;
+            stopwatch.lap_at(instant(now));
+        }
+
+        assert_eq!(stopwatch.max_lap(), Some(1_099));
+        assert_eq!(stopwatch.number_of_laps(), 10_000);
+        assert_eq!(stopwatch.total_time, now);
+        assert!(stopwatch.laps().is_empty());
+        assert_eq!(stopwatch.laps.capacity(), 0);
+
+        let stopwatch = stopwatch.pause().stop();
+        assert!(stopwatch.laps().is_empty());
+        assert_eq!(stopwatch.number_of_laps(), 10_001);
+    }
+
+    #[test]
+    fn peak_tracking_bookkeeping() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut stopwatch = Stopwatch::peak_tracking().start();
+        stopwatch.start_time = Some(instant(1_000));
+        stopwatch.set_auto_name_prefix("step-");
+
+        let alarm_calls = Arc::clone(&calls);
+        stopwatch.set_total_alarm(500, move || {
+            alarm_calls.fetch_add(1, Ordering::SeqCst);
+        });
+
+        stopwatch.lap_at(instant(1_100));
+        stopwatch.lap_at(instant(1_400));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        stopwatch.lap_at(instant(1_600));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        assert_eq!(stopwatch.next_sequence_id(), 3);
+        assert!(stopwatch.lap_names.is_empty());
+        assert_eq!(stopwatch.auto_name_prefix, Some((String::from("step-"), 3)));
+    }
+
+    #[test]
+    fn peak_tracking_summaries() {
+        let stopwatch = Stopwatch::peak_tracking();
+        assert_eq!(stopwatch.statistics(), LapStatistics::default());
+        assert_eq!(stopwatch.summary_line(), "0 laps, total 0ns");
+        assert_eq!(stopwatch.clone().transition::<Stopped>(None).confidence_banner(), "no laps");
+
+        let mut stopwatch = stopwatch.start();
+        stopwatch.start_time = Some(instant(1_000));
+        stopwatch.lap_at(instant(1_100));
+        stopwatch.lap_at(instant(1_400));
+        stopwatch.lap_at(instant(1_600));
+        let stopwatch = stopwatch.transition::<Stopped>(None);
+
+        let statistics = stopwatch.statistics();
+        assert_eq!(statistics.count, stopwatch.number_of_laps());
+        assert_eq!(statistics.count, 3);
+        assert_eq!(statistics.total, 600);
+        assert_eq!(statistics.max, 300);
+        assert!((statistics.mean - 200.0).abs() < f64::EPSILON);
+        assert_eq!(statistics.min, 0);
+        assert_eq!(stopwatch.summary_line(), "3 laps, total 600ns, mean 200ns, max 300ns");
+        assert_eq!(stopwatch.confidence_banner(), "mean 200ns over 3 laps");
+    }
+
     #[test]
     fn number_of_laps() {
         let mut stopwatch = Stopwatch::start_new();
@@ -381,6 +4034,220 @@ mod tests {
         assert_eq!(stopwatch.number_of_laps(), 3);
     }
 
+    #[test]
+    fn columns() {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.laps = vec![100, 200, 300];
+        let (indices, durations, cumulative) = stopwatch.columns();
+        assert_eq!(indices, vec![0, 1, 2]);
+        assert_eq!(durations, vec![100, 200, 300]);
+        assert_eq!(cumulative, vec![100, 300, 600]);
+    }
+
+    #[test]
+    fn count_above_percentile() {
+        let mut stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.count_above_percentile(90.0), 0);
+
+        stopwatch.laps = (1..101).rev().collect();
+        assert_eq!(stopwatch.count_above_percentile(90.0), 10);
+        assert_eq!(stopwatch.count_above_percentile(100.0), 0);
+
+        stopwatch.laps = vec![100, 100, 100, 100, 500];
+        assert_eq!(stopwatch.count_above_percentile(50.0), 1);
+    }
+
+    #[test]
+    fn cumulative_at() {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.laps = vec![100, 200, 300];
+        assert_eq!(stopwatch.cumulative_at(0), Some(100));
+        assert_eq!(stopwatch.cumulative_at(1), Some(300));
+        assert_eq!(stopwatch.cumulative_at(2), Some(600));
+        assert_eq!(stopwatch.cumulative_at(3), None);
+    }
+
+    #[test]
+    fn epoch_label() {
+        let mut stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.epoch_label(), None);
+
+        stopwatch.set_epoch_label("run-42");
+        let stopwatch = stopwatch.start().pause().resume().stop();
+        assert_eq!(stopwatch.epoch_label(), Some("run-42"));
+
+        let stopwatch = stopwatch.reset();
+        assert_eq!(stopwatch.epoch_label(), None);
+    }
+
+    #[test]
+    fn describe() {
+        let stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.describe(),
+                   "state: Initialized\nlaps: 0\ntotal time: 0ns\nrunning: false\npauses: 0\nrecording mode: interval");
+
+        let stopwatch = stopwatch.start().pause().resume();
+        let (_, stopwatch) = stopwatch.lap_and_pause();
+        let mut stopwatch = stopwatch.stop();
+        stopwatch.laps = vec![1_000, 500];
+        stopwatch.total_time = 1_500;
+        assert_eq!(stopwatch.describe(),
+                   "state: Stopped\nlaps: 2\ntotal time: 1.50µs\nrunning: false\npauses: 2\nrecording mode: interval");
+
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.set_recording_mode(RecordingMode::Cumulative);
+        assert!(stopwatch.describe().ends_with("\nrecording mode: cumulative"));
+    }
+
+    #[test]
+    fn sparkline() {
+        let mut stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.sparkline(), "");
+
+        stopwatch.laps = vec![1, 4, 8];
+        assert_eq!(stopwatch.sparkline(), "▁▄█");
+
+        stopwatch.laps = vec![0, 1, 2, 3, 4, 5, 6, 7, u64::MAX];
+        assert_eq!(stopwatch.sparkline(), "▁▁▁▁▁▁▁▁█");
+
+        stopwatch.laps = vec![500; 3];
+        assert_eq!(stopwatch.sparkline(), "▄▄▄");
+    }
+
+    #[test]
+    fn state() {
+        let stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.state(), "Initialized");
+        let stopwatch = stopwatch.start();
+        assert_eq!(stopwatch.state(), "Running");
+        let stopwatch = stopwatch.pause();
+        assert_eq!(stopwatch.state(), "Paused");
+        let stopwatch = stopwatch.stop();
+        assert_eq!(stopwatch.state(), "Stopped");
+    }
+
+    #[test]
+    fn first_lap() {
+        let mut stopwatch = Stopwatch::start_new();
+        assert_eq!(stopwatch.first_lap(), None);
+
+        let lap: u64 = stopwatch.lap();
+        stopwatch.lap();
+        stopwatch.lap();
+        assert_eq!(stopwatch.first_lap(), Some(lap));
+    }
+
+    #[test]
+    fn group_laps_by() {
+        let mut stopwatch = Stopwatch::new();
+        assert!(stopwatch.group_laps_by(|index, _| index % 2 == 0).is_empty());
+
+        stopwatch.laps = vec![10, 20, 30, 40, 50];
+        let groups = stopwatch.group_laps_by(|index, _| index % 2 == 0);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[&true], vec![10, 30, 50]);
+        assert_eq!(groups[&false], vec![20, 40]);
+    }
+
+    #[test]
+    fn histogram_with_edges() {
+        let mut stopwatch = Stopwatch::new();
+        let edges: [u64; 2] = [1_000_000, 10_000_000];
+        assert_eq!(stopwatch.histogram_with_edges(&edges), vec![0, 0, 0]);
+
+        stopwatch.laps = vec![500_000, 999_999, 1_000_000, 5_000_000, 9_999_999, 10_000_000, 250_000_000];
+        assert_eq!(stopwatch.histogram_with_edges(&edges), vec![2, 3, 2]);
+        assert_eq!(stopwatch.histogram_with_edges(&[]), vec![7]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn histogram_with_unsorted_edges() {
+        let stopwatch = Stopwatch::new();
+        stopwatch.histogram_with_edges(&[10_000_000, 1_000_000]);
+    }
+
+    #[test]
+    fn improvement_needed_for_percentile() {
+        let mut stopwatch = Stopwatch::new();
+        assert_eq!(stopwatch.improvement_needed_for_percentile(99.0, 150), None);
+
+        stopwatch.laps = vec![100; 99];
+        stopwatch.laps.push(200);
+        assert_eq!(stopwatch.improvement_needed_for_percentile(99.0, 150), Some(0));
+        assert_eq!(stopwatch.improvement_needed_for_percentile(100.0, 150), Some(50));
+
+        stopwatch.laps = vec![120, 200, 90];
+        assert_eq!(stopwatch.improvement_needed_for_percentile(99.0, 150), Some(50));
+        assert_eq!(stopwatch.improvement_needed_for_percentile(50.0, 150), Some(0));
+    }
+
+    #[test]
+    fn is_converged() {
+        let mut stopwatch = Stopwatch::new();
+        assert!(!stopwatch.is_converged(0.05, 0));
+
+        stopwatch.laps = vec![1_000, 1_010, 990, 1_005, 995];
+        assert!(stopwatch.is_converged(0.05, 5));
+        assert!(!stopwatch.is_converged(0.05, 6));
+    }
+
+    #[test]
+    fn is_converged_noisy() {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.laps = vec![1_000, 3_000, 200, 5_000, 700];
+        assert!(!stopwatch.is_converged(0.05, 5));
+    }
+
+    #[test]
+    fn is_empty() {
+        let stopwatch = Stopwatch::new();
+        assert!(stopwatch.is_empty());
+
+        let stopwatch = stopwatch.start();
+        assert!(stopwatch.is_empty());
+
+        let stopwatch = stopwatch.pause();
+        assert!(stopwatch.is_empty());
+
+        let mut stopwatch = stopwatch.resume();
+        stopwatch.lap();
+        assert!(!stopwatch.is_empty());
+
+        let (_, stopwatch) = stopwatch.lap_and_pause();
+        assert!(!stopwatch.is_empty());
+        assert!(!stopwatch.stop().is_empty());
+    }
+
+    #[test]
+    fn pause_and_resume_keep_number_of_laps() {
+        let mut stopwatch = Stopwatch::start_new();
+        let lap: u64 = stopwatch.lap();
+        thread::sleep(Duration::from_millis(10));
+
+        let stopwatch = stopwatch.pause();
+        assert_eq!(stopwatch.number_of_laps(), 1);
+        assert_eq!(stopwatch.laps(), &vec![lap]);
+        assert!(stopwatch.paused_lap.unwrap() >= 10_000_000);
+
+        let mut stopwatch = stopwatch.resume();
+        assert_eq!(stopwatch.number_of_laps(), 1);
+        assert!(stopwatch.paused_lap.is_none());
+        assert!(stopwatch.lap() >= 10_000_000);
+
+        let (_, stopwatch) = stopwatch.lap_and_pause();
+        assert_eq!(stopwatch.number_of_laps(), 3);
+        let stopwatch = stopwatch.resume();
+        assert_eq!(stopwatch.number_of_laps(), 3);
+
+        let mut stopwatch = Stopwatch::peak_tracking().start();
+        stopwatch.lap();
+        let stopwatch = stopwatch.pause();
+        assert_eq!(stopwatch.number_of_laps(), 1);
+        assert!(!stopwatch.is_empty());
+        assert_eq!(stopwatch.stop().number_of_laps(), 2);
+    }
+
     #[test]
     fn is_running() {
         let stopwatch = Stopwatch::new();
@@ -390,6 +4257,40 @@ mod tests {
         assert!(stopwatch.is_running());
     }
 
+    #[test]
+    fn is_within() {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.total_time = 1_000;
+
+        assert!(stopwatch.is_within(1_000, 0));
+        assert!(stopwatch.is_within(1_050, 50));
+        assert!(stopwatch.is_within(950, 50));
+        assert!(!stopwatch.is_within(1_051, 50));
+        assert!(!stopwatch.is_within(949, 50));
+
+        stopwatch.assert_within(1_020, 20);
+    }
+
+    #[test]
+    #[should_panic]
+    fn assert_within_outside_tolerance() {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.total_time = 1_000;
+        stopwatch.assert_within(1_100, 50);
+    }
+
+    #[test]
+    fn annotate() {
+        let mut stopwatch = Stopwatch::start_new();
+        stopwatch.annotate("ran on battery");
+        stopwatch.annotate(String::from("debug build"));
+        let stopwatch = stopwatch.stop();
+        assert_eq!(stopwatch.notes(), &["ran on battery", "debug build"]);
+
+        let stopwatch = stopwatch.reset();
+        assert!(stopwatch.notes().is_empty());
+    }
+
     #[test]
     fn fmt_display() {
         let mut stopwatch = Stopwatch::new();
@@ -0,0 +1,91 @@
+// Copyright 2017 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! A group of stopwatches that are paused and resumed together.
+
+use stopwatch::Paused;
+use stopwatch::Running;
+use stopwatch::Stopwatch;
+
+/// A group of stopwatches in the state `State` that change their state together, e.g. one stopwatch per subsystem.
+///
+/// Like [`Stopwatch`](struct.Stopwatch.html), the group's state is defined on the type level.
+#[derive(Clone, Debug)]
+pub struct StopwatchGroup<State> {
+    /// The stopwatches in the group.
+    stopwatches: Vec<Stopwatch<State>>,
+}
+
+impl<State> StopwatchGroup<State> {
+    /// Get the stopwatches in the group, in the order they have been added.
+    pub fn stopwatches(&self) -> &[Stopwatch<State>] {
+        &self.stopwatches
+    }
+
+    /// Get the [total time](struct.Stopwatch.html#method.total_time) of each stopwatch in the group, in the order the
+    /// stopwatches have been added.
+    pub fn total_times(&self) -> Vec<u64> {
+        self.stopwatches.iter()
+            .map(Stopwatch::total_time)
+            .collect()
+    }
+}
+
+impl StopwatchGroup<Running> {
+    /// Initialize a new group of the given running stopwatches.
+    pub fn new(stopwatches: Vec<Stopwatch<Running>>) -> StopwatchGroup<Running> {
+        StopwatchGroup {
+            stopwatches,
+        }
+    }
+
+    /// Pause all stopwatches in the group.
+    pub fn pause_all(self) -> StopwatchGroup<Paused> {
+        StopwatchGroup {
+            stopwatches: self.stopwatches.into_iter()
+                .map(Stopwatch::pause)
+                .collect(),
+        }
+    }
+}
+
+impl StopwatchGroup<Paused> {
+    /// Resume all stopwatches in the group.
+    pub fn resume_all(self) -> StopwatchGroup<Running> {
+        StopwatchGroup {
+            stopwatches: self.stopwatches.into_iter()
+                .map(Stopwatch::resume)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use stopwatch::Stopwatch;
+    use super::StopwatchGroup;
+
+    #[test]
+    fn pause_all_and_resume_all() {
+        let group = StopwatchGroup::new(vec![Stopwatch::start_new(), Stopwatch::start_new()]);
+        assert_eq!(group.stopwatches().len(), 2);
+
+        let pause = Duration::from_millis(100);
+        let group = group.pause_all();
+        let paused_times: Vec<u64> = group.total_times();
+        thread::sleep(pause);
+        assert_eq!(group.total_times(), paused_times);
+
+        let group = group.resume_all();
+        assert!(group.stopwatches().iter().all(Stopwatch::is_running));
+        for total_time in group.total_times() {
+            assert!(u128::from(total_time) < pause.as_nanos());
+        }
+    }
+}
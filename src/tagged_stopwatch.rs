@@ -0,0 +1,62 @@
+// Copyright 2017 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! A stopwatch associating a typed payload with each lap.
+
+use stopwatch::Running;
+use stopwatch::Stopwatch;
+
+/// A running stopwatch that associates a payload of type `T` with each lap, e.g. the size of the processed input.
+#[derive(Clone, Debug)]
+pub struct TaggedStopwatch<T> {
+    /// The underlying stopwatch measuring the laps.
+    stopwatch: Stopwatch<Running>,
+
+    /// All lap measurements with their payloads.
+    entries: Vec<(u64, T)>,
+}
+
+impl<T> TaggedStopwatch<T> {
+    /// Initialize a new tagged stopwatch and start it.
+    pub fn start_new() -> TaggedStopwatch<T> {
+        TaggedStopwatch {
+            stopwatch: Stopwatch::start_new(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Start a new lap. Save the last lap's time together with the given `payload` and return the lap's time.
+    pub fn lap_with(&mut self, payload: T) -> u64 {
+        let lap: u64 = self.stopwatch.lap();
+        self.entries.push((lap, payload));
+        lap
+    }
+
+    /// Get the list of all measured lap times with their payloads in the order the laps were timed.
+    pub fn entries(&self) -> &[(u64, T)] {
+        &self.entries
+    }
+
+    /// Get the underlying stopwatch.
+    pub fn stopwatch(&self) -> &Stopwatch<Running> {
+        &self.stopwatch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TaggedStopwatch;
+
+    #[test]
+    fn lap_with() {
+        let mut stopwatch: TaggedStopwatch<usize> = TaggedStopwatch::start_new();
+        let lap_1: u64 = stopwatch.lap_with(1_024);
+        let lap_2: u64 = stopwatch.lap_with(4_096);
+
+        assert_eq!(stopwatch.entries(), &[(lap_1, 1_024), (lap_2, 4_096)]);
+        assert_eq!(stopwatch.stopwatch().laps(), &vec![lap_1, lap_2]);
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright 2017 Bastian Meyer
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Units of time in which measurements can be expressed.
+
+/// A unit of time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TimeUnit {
+    /// Nanoseconds, i.e. `10^-9` seconds.
+    Nanoseconds,
+
+    /// Microseconds, i.e. `10^-6` seconds.
+    Microseconds,
+
+    /// Milliseconds, i.e. `10^-3` seconds.
+    Milliseconds,
+
+    /// Seconds.
+    Seconds,
+}
+
+impl TimeUnit {
+    /// Get the number of nanoseconds in one of this unit.
+    pub fn nanoseconds(self) -> u64 {
+        match self {
+            TimeUnit::Nanoseconds => 1,
+            TimeUnit::Microseconds => 1_000,
+            TimeUnit::Milliseconds => 1_000_000,
+            TimeUnit::Seconds => 1_000_000_000,
+        }
+    }
+
+    /// Get the largest unit in which the given number of nanoseconds is at least `1`, or nanoseconds for values
+    /// below one nanosecond.
+    pub(crate) fn for_nanoseconds(nanoseconds: f64) -> TimeUnit {
+        [TimeUnit::Seconds, TimeUnit::Milliseconds, TimeUnit::Microseconds].iter()
+            .cloned()
+            .find(|unit| nanoseconds >= unit.nanoseconds() as f64)
+            .unwrap_or(TimeUnit::Nanoseconds)
+    }
+
+    /// Get the symbol of this unit.
+    pub(crate) fn symbol(self) -> &'static str {
+        match self {
+            TimeUnit::Nanoseconds => "ns",
+            TimeUnit::Microseconds => "µs",
+            TimeUnit::Milliseconds => "ms",
+            TimeUnit::Seconds => "s",
+        }
+    }
+
+    /// Round the given number of nanoseconds to the nearest multiple of this unit.
+    ///
+    /// Values exactly halfway between two multiples are rounded up.
+    pub(crate) fn round(self, nanoseconds: u64) -> u64 {
+        let unit: u64 = self.nanoseconds();
+        let remainder: u64 = nanoseconds % unit;
+        let rounded_down: u64 = nanoseconds - remainder;
+        if remainder >= unit - unit / 2 {
+            rounded_down.saturating_add(unit)
+        } else {
+            rounded_down
+        }
+    }
+}
+
+/// Format the given number of nanoseconds for humans, using the largest unit in which the value is at least `1`.
+///
+/// Nanoseconds are formatted without decimal places, all other units with two decimal places.
+pub(crate) fn format_nanoseconds(nanoseconds: f64) -> String {
+    let unit: TimeUnit = TimeUnit::for_nanoseconds(nanoseconds);
+    match unit {
+        TimeUnit::Nanoseconds => format!("{value:.0}{symbol}", value = nanoseconds, symbol = unit.symbol()),
+        _ => format!("{value:.2}{symbol}", value = nanoseconds / unit.nanoseconds() as f64, symbol = unit.symbol()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TimeUnit;
+
+    #[test]
+    fn nanoseconds() {
+        assert_eq!(TimeUnit::Nanoseconds.nanoseconds(), 1);
+        assert_eq!(TimeUnit::Microseconds.nanoseconds(), 1_000);
+        assert_eq!(TimeUnit::Milliseconds.nanoseconds(), 1_000_000);
+        assert_eq!(TimeUnit::Seconds.nanoseconds(), 1_000_000_000);
+    }
+
+    #[test]
+    fn for_nanoseconds() {
+        assert_eq!(TimeUnit::for_nanoseconds(0.0), TimeUnit::Nanoseconds);
+        assert_eq!(TimeUnit::for_nanoseconds(999.0), TimeUnit::Nanoseconds);
+        assert_eq!(TimeUnit::for_nanoseconds(1_000.0), TimeUnit::Microseconds);
+        assert_eq!(TimeUnit::for_nanoseconds(2_500_000.0), TimeUnit::Milliseconds);
+        assert_eq!(TimeUnit::for_nanoseconds(61_000_000_000.0), TimeUnit::Seconds);
+    }
+
+    #[test]
+    fn format_nanoseconds() {
+        assert_eq!(super::format_nanoseconds(600.0), "600ns");
+        assert_eq!(super::format_nanoseconds(166.67), "167ns");
+        assert_eq!(super::format_nanoseconds(200_000.0), "200.00µs");
+        assert_eq!(super::format_nanoseconds(1_234_567.0), "1.23ms");
+        assert_eq!(super::format_nanoseconds(3_500_000_000.0), "3.50s");
+    }
+
+    #[test]
+    fn round() {
+        assert_eq!(TimeUnit::Nanoseconds.round(1_234), 1_234);
+        assert_eq!(TimeUnit::Microseconds.round(1_499), 1_000);
+        assert_eq!(TimeUnit::Microseconds.round(1_500), 2_000);
+        assert_eq!(TimeUnit::Milliseconds.round(400_000), 0);
+        assert_eq!(TimeUnit::Seconds.round(2_600_000_000), 3_000_000_000);
+    }
+}